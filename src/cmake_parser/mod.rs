@@ -32,7 +32,8 @@ pub fn parse_tests_from_file(p: &Path) -> Result<Vec<RpTest>> {
     let data = fs::read_to_string(p)?;
     let mut tests = Vec::new();
     let ctx = parse_unit(&data);
-    for (_, test) in ctx.tests {
+    for (_, mut test) in ctx.tests {
+        test.cmake_file = p.to_owned();
         tests.push(test);
     }
     Ok(tests)
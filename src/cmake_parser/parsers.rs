@@ -99,6 +99,7 @@ fn parse_rp_test(input: &str) -> IResult<&str, RpTest> {
             sources: find_test_sources(&res),
             kind,
             tests: Default::default(),
+            cmake_file: Default::default(),
         },
     ))
 }
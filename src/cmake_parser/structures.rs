@@ -1,10 +1,14 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+use crate::location::Location;
+
 #[derive(Debug, Default, PartialEq)]
 pub(crate) struct SourceSet {
     pub name: String,
@@ -20,7 +24,7 @@ impl SourceSet {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ValueEnum)]
 pub enum TestKind {
     Unit,
     Fixture,
@@ -37,12 +41,72 @@ impl Display for TestKind {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum TestFramework {
+    Boost,
+    Seastar,
+    GTest,
+}
+
+impl TestFramework {
+    /// The name used for this framework in command templates, both as the value of
+    /// `{{framework}}` and as the suffix of a framework specific `run_<name>` template.
+    pub fn template_name(&self) -> &'static str {
+        match self {
+            TestFramework::Boost => "boost",
+            TestFramework::Seastar => "seastar",
+            TestFramework::GTest => "gtest",
+        }
+    }
+}
+
+impl Display for TestFramework {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TestFramework::Boost => write!(f, "Boost.Test"),
+            TestFramework::Seastar => write!(f, "Seastar"),
+            TestFramework::GTest => write!(f, "GTest"),
+        }
+    }
+}
+
+/// A single test case found in a C++ source file. For gtest tests `suite` is the test suite and the
+/// test is addressed as `Suite.Name`. For Boost and Seastar tests it is the `/` separated path of
+/// the enclosing `BOOST_AUTO_TEST_SUITE` blocks, and the test is addressed as `outer/inner/name`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct CcTestCase {
+    pub name: String,
+    pub suite: Option<String>,
+    pub framework: TestFramework,
+    /// Set for `TEST_P` and `TYPED_TEST`, whose runtime names carry an instantiation prefix or a
+    /// type parameter around `Suite.Name`, and for `BOOST_AUTO_TEST_CASE_TEMPLATE`, whose cases are
+    /// named `name<T>`.
+    pub parameterized: bool,
+    pub location: Location,
+}
+
+impl CcTestCase {
+    pub fn qualified_name(&self) -> String {
+        match (&self.suite, &self.framework) {
+            (Some(suite), TestFramework::GTest) => format!("{suite}.{}", self.name),
+            (Some(suite), _) => format!("{suite}/{}", self.name),
+            (None, _) => self.name.clone(),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || self.qualified_name() == name
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RpTest {
     pub name: String,
     pub sources: HashSet<String>,
     pub kind: TestKind,
-    pub tests: HashSet<String>,
+    pub tests: HashSet<CcTestCase>,
+    /// The `CMakeLists.txt` declaring this test, source paths are relative to its directory.
+    pub cmake_file: PathBuf,
 }
 
 impl Display for RpTest {
@@ -52,6 +116,40 @@ impl Display for RpTest {
 }
 
 impl RpTest {
+    pub fn binary_name(&self) -> String {
+        match self.kind {
+            TestKind::Unit => format!("{}_rpunit", self.name),
+            TestKind::Fixture => format!("{}_rpfixture", self.name),
+            TestKind::Bench => format!("{}_rpbench", self.name),
+        }
+    }
+
+    pub fn source_paths(&self) -> Vec<PathBuf> {
+        let dir = self.cmake_file.parent().unwrap_or(Path::new(""));
+        let mut paths: Vec<_> = self.sources.iter().map(|s| dir.join(s)).collect();
+        paths.sort();
+        paths
+    }
+
+    /// The cases matching the name, ordered by their qualified names. A qualified name matches a
+    /// single case, while a leaf name may match cases from several suites.
+    pub(crate) fn find_cases(&self, name: &str) -> Vec<&CcTestCase> {
+        if let Some(case) = self.tests.iter().find(|t| t.qualified_name() == name) {
+            return vec![case];
+        }
+        let mut cases: Vec<_> = self.tests.iter().filter(|t| t.matches(name)).collect();
+        cases.sort_by_key(|t| t.qualified_name());
+        cases
+    }
+
+    /// The case with the name, if exactly one case matches it.
+    pub(crate) fn find_case(&self, name: &str) -> Option<&CcTestCase> {
+        match self.find_cases(name)[..] {
+            [case] => Some(case),
+            _ => None,
+        }
+    }
+
     pub(crate) fn eval(&self, variables: &HashMap<String, String>) -> RpTest {
         let mut clone = self.clone();
         clone.name = self.eval_name(variables);
@@ -1,13 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Result;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use xdg::BaseDirectories;
 
+use crate::cmake_parser::structures::TestFramework;
+
 pub fn get_prefix() -> Result<BaseDirectories> {
     BaseDirectories::with_prefix("snipe").map_err(anyhow::Error::from)
 }
@@ -99,13 +102,13 @@ where
     T: WritableConfig + Default + DeserializeOwned + Serialize,
 {
     if T::is_config_present()? {
-        println!(
+        eprintln!(
             "loading configuration from {}",
             T::config_path()?.to_string_lossy()
         );
         load_existing_configuration::<T>()
     } else {
-        println!(
+        eprintln!(
             "storing default configuration in {}",
             T::config_path()?.to_string_lossy()
         );
@@ -126,9 +129,33 @@ where
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// How commands are run. `Pty` runs them on a pseudo terminal owned by snipe, falling back to
+/// pipes when stdout is not a terminal. `Teetty` runs them through the external `teetty` binary.
+/// `Plain` runs them with snipe's own stdin, stdout and stderr, without touching their output.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Runner {
+    #[default]
+    Pty,
+    Teetty,
+    Plain,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CommandRunConfig {
     pub command_mappings: HashMap<String, String>,
+    #[serde(default)]
+    pub runner: Runner,
+    /// Default timeouts in seconds, by the kind of test: `unit`, `fixture`, `bench` or `ducktape`
+    #[serde(default)]
+    pub timeouts: HashMap<String, u64>,
+}
+
+impl CommandRunConfig {
+    pub fn default_timeout(&self, kind: &str) -> Option<Duration> {
+        self.timeouts
+            .get(&kind.to_lowercase())
+            .map(|secs| Duration::from_secs(*secs))
+    }
 }
 
 impl WritableConfig for CommandRunConfig {
@@ -143,7 +170,8 @@ impl Default for CommandRunConfig {
         let mut command_mappings = HashMap::new();
         command_mappings.insert(
             "duck".to_owned(),
-            r#"task rp:run-ducktape-tests DUCKTAPE_ARGS="{{test_path}} {{test_args}}""#.to_owned(),
+            r#"task rp:run-ducktape-tests DUCKTAPE_ARGS="{{test_path}} {{test_args}} {{extra_args}}""#
+                .to_owned(),
         );
         command_mappings.insert(
             "compile".to_owned(),
@@ -153,14 +181,82 @@ impl Default for CommandRunConfig {
             "run".to_owned(), 
             "./tools/cmake_test.py --binary {{pwd}}/vbuild/{{build_type}}/clang/bin/{{test_obj}} {{test_tag_arg}} -- -c1".to_owned()
         );
-        Self { command_mappings }
+        Self {
+            command_mappings,
+            runner: Runner::default(),
+            timeouts: HashMap::new(),
+        }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// A macro which declares a C++ test case. `name_arg` is the zero based index of the macro argument
+/// holding the test name, and `suite_arg` the index of the argument holding the suite, if any.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TestMacro {
+    pub name: String,
+    pub framework: TestFramework,
+    pub name_arg: usize,
+    #[serde(default)]
+    pub suite_arg: Option<usize>,
+    #[serde(default)]
+    pub parameterized: bool,
+}
+
+impl TestMacro {
+    fn new(name: &str, framework: TestFramework, name_arg: usize) -> Self {
+        Self {
+            name: name.to_owned(),
+            framework,
+            name_arg,
+            suite_arg: None,
+            parameterized: false,
+        }
+    }
+
+    fn gtest(name: &str, parameterized: bool) -> Self {
+        Self {
+            suite_arg: Some(0),
+            parameterized,
+            ..Self::new(name, TestFramework::GTest, 1)
+        }
+    }
+}
+
+fn default_test_macros() -> Vec<TestMacro> {
+    vec![
+        TestMacro::new("FIXTURE_TEST", TestFramework::Seastar, 0),
+        TestMacro::new("FIXTURE_TEST_EXPECTED_FAILURES", TestFramework::Seastar, 0),
+        TestMacro::new("SEASTAR_THREAD_TEST_CASE", TestFramework::Seastar, 0),
+        TestMacro::new(
+            "SEASTAR_THREAD_TEST_CASE_EXPECTED_FAILURES",
+            TestFramework::Seastar,
+            0,
+        ),
+        TestMacro::new("SEASTAR_TEST_CASE", TestFramework::Seastar, 0),
+        TestMacro::new("BOOST_AUTO_TEST_CASE", TestFramework::Boost, 0),
+        TestMacro::new("BOOST_FIXTURE_TEST_CASE", TestFramework::Boost, 0),
+        TestMacro::new("BOOST_DATA_TEST_CASE", TestFramework::Boost, 0),
+        TestMacro {
+            parameterized: true,
+            ..TestMacro::new("BOOST_AUTO_TEST_CASE_TEMPLATE", TestFramework::Boost, 0)
+        },
+        TestMacro::gtest("TEST", false),
+        TestMacro::gtest("TEST_F", false),
+        TestMacro::gtest("TEST_P", true),
+        TestMacro::gtest("TYPED_TEST", true),
+    ]
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ScanConfig {
     pub cc_test_root: String,
     pub py_test_root: String,
+    #[serde(default = "default_test_macros")]
+    pub cc_test_macros: Vec<TestMacro>,
+    /// Preprocessor symbols treated as defined when deciding which `#ifdef` regions of C++ test
+    /// sources to scan.
+    #[serde(default)]
+    pub cc_defines: HashSet<String>,
 }
 
 impl WritableConfig for ScanConfig {
@@ -175,11 +271,13 @@ impl Default for ScanConfig {
         Self {
             cc_test_root: "src/v".to_owned(),
             py_test_root: "tests/rptest".to_owned(),
+            cc_test_macros: default_test_macros(),
+            cc_defines: Default::default(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CommandEnv {
     pub envs: HashMap<String, String>,
 }
@@ -0,0 +1,187 @@
+use std::env;
+use std::io::{stdout, IsTerminal};
+
+const SCORE_MATCH: i64 = 16;
+/// Added for a match at the start of the candidate or of a word in it, eg after `_` or `/`.
+const BONUS_BOUNDARY: i64 = 8;
+/// Added for a match right after the previous one.
+const BONUS_CONSECUTIVE: i64 = 8;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// A match of a pattern against a candidate, with the indices of the candidate's chars which
+/// matched the pattern.
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+fn is_boundary(chars: &[char], index: usize) -> bool {
+    match index.checked_sub(1).map(|prev| chars[prev]) {
+        None => true,
+        Some(prev) if !prev.is_alphanumeric() => true,
+        Some(prev) => prev.is_lowercase() && chars[index].is_uppercase(),
+    }
+}
+
+/// Scores the candidate against the pattern in the style of fzf. Every char of the pattern must
+/// appear in the candidate in order, ignoring case. Matches at word boundaries and runs of
+/// consecutive matches score higher, gaps between matches lower.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let (m, n) = (pattern.len(), chars.len());
+    if m == 0 || m > n {
+        return None;
+    }
+
+    // scores[i][j] is the best score for the first i + 1 pattern chars with the last one matched
+    // at j, and from[i][j] is where the previous pattern char was matched for that score
+    let mut scores = vec![vec![None; n]; m];
+    let mut from = vec![vec![0; n]; m];
+    for i in 0..m {
+        // the best score for the previous pattern char followed by a gap which ends before j
+        let mut gapped: Option<(i64, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let opened = scores[i - 1][j - 2].map(|s: i64| (s - PENALTY_GAP_START, j - 2));
+                let extended = gapped.map(|(s, k)| (s - PENALTY_GAP_EXTENSION, k));
+                gapped = match (opened, extended) {
+                    (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                    (a, b) => a.or(b),
+                };
+            }
+
+            if chars[j].to_ascii_lowercase() != pattern[i] {
+                continue;
+            }
+
+            let bonus = if is_boundary(&chars, j) {
+                BONUS_BOUNDARY
+            } else {
+                0
+            };
+            let previous = if i == 0 {
+                Some((0, 0))
+            } else {
+                let consecutive = scores[i - 1][j - 1].map(|s| (s + BONUS_CONSECUTIVE, j - 1));
+                match (consecutive, gapped) {
+                    (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                    (a, b) => a.or(b),
+                }
+            };
+            if let Some((score, k)) = previous {
+                scores[i][j] = Some(score + SCORE_MATCH + bonus);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut end, score) = scores[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|s| (j, s)))
+        .max_by_key(|(j, score)| (*score, std::cmp::Reverse(*j)))?;
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = end;
+        end = from[i][end];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+fn color_enabled() -> bool {
+    stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Marks the matched chars of the text in bold when writing to a terminal.
+pub fn highlight(text: &str, positions: &[usize]) -> String {
+    if !color_enabled() {
+        return text.to_owned();
+    }
+
+    let mut out = String::with_capacity(text.len() * 2);
+    for (index, c) in text.chars().enumerate() {
+        if positions.contains(&index) {
+            out.push_str(&format!("\x1b[1;32m{c}\x1b[0m"));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate)
+            .unwrap_or_else(|| panic!("{pattern} should match {candidate}"))
+            .score
+    }
+
+    fn positions(pattern: &str, candidate: &str) -> Vec<usize> {
+        fuzzy_match(pattern, candidate)
+            .unwrap_or_else(|| panic!("{pattern} should match {candidate}"))
+            .positions
+    }
+
+    #[test]
+    fn no_match_without_a_subsequence() {
+        assert!(fuzzy_match("xyz", "test_foo").is_none());
+        assert!(fuzzy_match("of", "foo").is_none());
+        assert!(fuzzy_match("", "foo").is_none());
+    }
+
+    #[test]
+    fn no_match_for_a_pattern_longer_than_the_candidate() {
+        assert!(fuzzy_match("food", "foo").is_none());
+    }
+
+    #[test]
+    fn matching_ignores_case() {
+        assert_eq!(positions("TF", "test_foo"), [0, 5]);
+        assert_eq!(positions("tf", "TEST_FOO"), [0, 5]);
+    }
+
+    #[test]
+    fn positions_prefer_word_boundaries() {
+        assert_eq!(positions("tf", "test_foo"), [0, 5]);
+        assert_eq!(positions("fb", "fooBar"), [0, 3]);
+    }
+
+    #[test]
+    fn positions_follow_the_best_alignment() {
+        // `a` at 0 is also a boundary, but `ab` together at 2 scores higher
+        assert_eq!(positions("ab", "a_ab"), [2, 3]);
+        assert_eq!(
+            positions("nest", "outer/inner/test_nested"),
+            [17, 18, 19, 20]
+        );
+    }
+
+    #[test]
+    fn consecutive_matches_rank_higher() {
+        assert!(score("bar", "xbarx") > score("bar", "bxaxr"));
+    }
+
+    #[test]
+    fn boundary_matches_rank_higher() {
+        assert!(score("ab", "x_ab") > score("ab", "xxab"));
+        assert!(score("fb", "fooBar") > score("fb", "foobar"));
+    }
+
+    #[test]
+    fn longer_gaps_rank_lower() {
+        assert!(score("ab", "axb") > score("ab", "axxb"));
+        assert!(score("ab", "axxb") > score("ab", "axxxxxb"));
+    }
+
+    #[test]
+    fn candidates_rank_by_consecutive_boundary_and_gap_scores() {
+        let mut candidates = vec!["axxxb", "axb", "a_b", "ab"];
+        candidates.sort_by_key(|c| std::cmp::Reverse(score("ab", c)));
+        assert_eq!(candidates, ["ab", "a_b", "axb", "axxxb"]);
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::cmake_parser::structures::{RpTest, TestKind};
+use crate::config::{create_data_file_handle, get_data_file_handle};
+use crate::py_parser::ClassWithTests;
+use crate::shell_commands::{Selection, ShellCommand, TIMED_OUT_EXIT_CODE};
+
+const HISTORY_FNAME: &str = "history.json";
+
+/// The oldest records are dropped once the history grows past this many runs.
+const MAX_HISTORY_RECORDS: usize = 200;
+
+/// The C++ binary or ducktape class a test was run from. Only what is needed to list the run is
+/// kept, the history is rewritten on every run.
+#[derive(Serialize, Deserialize, Clone)]
+pub enum RunTarget {
+    Binary { name: String, kind: TestKind },
+    Class { name: String, source_path: PathBuf },
+}
+
+impl From<&RpTest> for RunTarget {
+    fn from(test: &RpTest) -> Self {
+        RunTarget::Binary {
+            name: test.binary_name(),
+            kind: test.kind.clone(),
+        }
+    }
+}
+
+impl From<&ClassWithTests> for RunTarget {
+    fn from(test: &ClassWithTests) -> Self {
+        RunTarget::Class {
+            name: test.class_name.clone(),
+            source_path: test.source_path.clone(),
+        }
+    }
+}
+
+impl Display for RunTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RunTarget::Binary { name, kind } => write!(f, "{name} ({kind})"),
+            RunTarget::Class { name, source_path } => {
+                write!(f, "{}::{name}", source_path.to_string_lossy())
+            }
+        }
+    }
+}
+
+/// A single run of a test, with enough information to run it again without looking it up.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    pub test_name: String,
+    pub target: RunTarget,
+    pub selection: Selection,
+    pub commands: Vec<ShellCommand>,
+    pub exit_code: i32,
+    pub started_at: DateTime<Local>,
+    pub duration_ms: u64,
+    pub log: PathBuf,
+}
+
+impl RunRecord {
+    pub fn passed(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.exit_code == TIMED_OUT_EXIT_CODE
+    }
+
+    /// Whether the run was of the named test or target. A test can be named by its leaf or its
+    /// qualified name, including when it ran in a batch with other tests from the same binary.
+    pub fn is_run_of(&self, name: &str) -> bool {
+        self.test_name == name
+            || self
+                .test_name
+                .split(',')
+                .any(|member| member == name || member.rsplit(['/', '.']).next() == Some(name))
+    }
+}
+
+pub fn load_history() -> Result<Vec<RunRecord>> {
+    match get_data_file_handle(HISTORY_FNAME)? {
+        None => Ok(Vec::new()),
+        Some(handle) => Ok(serde_json::from_reader(handle).unwrap_or_else(|err| {
+            eprintln!("ignoring unreadable run history: {err}");
+            Vec::new()
+        })),
+    }
+}
+
+pub fn record_run(record: RunRecord) -> Result<()> {
+    let mut history = load_history()?;
+    history.push(record);
+    let excess = history.len().saturating_sub(MAX_HISTORY_RECORDS);
+    history.drain(..excess);
+
+    let mut handle = create_data_file_handle(HISTORY_FNAME)?;
+    handle.write_all(serde_json::to_string_pretty(&history)?.as_bytes())?;
+    Ok(())
+}
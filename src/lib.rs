@@ -1,25 +1,45 @@
 use std::collections::HashMap;
+use std::env;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::fs::{self};
-use std::io::{stdin, stdout, Write};
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
 
-use anyhow::{anyhow, Result};
-use clap::{command, ArgGroup, Parser};
+use anyhow::{anyhow, Context, Result};
+use clap::{command, ArgGroup, Parser, Subcommand};
 
-use cmake_parser::structures::RpTest;
+use cmake_parser::structures::{RpTest, TestFramework, TestKind as BinaryKind};
+use fuzzy::{fuzzy_match, highlight};
+use history::RunTarget;
+use location::Location;
+use pattern::NamePattern;
+use picker::{select_from_list, Pick};
 use py_parser::ClassWithTests;
 use scanners::{cmake, python};
 
 use crate::config::{
     create_data_file_handle, get_data_file_handle, load_configuration, CommandEnv,
-    CommandRunConfig, ScanConfig,
+    CommandRunConfig, Runner, ScanConfig,
+};
+use crate::shell_commands::{
+    build_cc_command, build_py_command, rerun, run_cc_test, run_py_test, run_template_for,
+    BatchState, DryRun, Repeat, RunOptions, Selection,
 };
-use crate::shell_commands::{run_cc_test, run_py_test};
 
 mod cmake_parser;
 pub mod config;
+mod fuzzy;
+mod history;
+mod location;
+mod pattern;
+mod picker;
+mod process_group;
+mod pty;
 mod py_parser;
+mod run_log;
 pub mod shell_commands;
 
 mod scanners;
@@ -27,35 +47,17 @@ mod scanners;
 const CC_DB_FNAME: &'static str = "cc.json";
 const PY_DB_FNAME: &'static str = "py.json";
 
-fn select_from_list<T>(items: Vec<T>, name: &str) -> Result<Option<T>>
-where
-    T: Display,
-{
-    println!("Multiple matches found for {name}");
-    loop {
-        println!("Please select one of the following matching items (q to quit): ");
-        for (index, item) in items.iter().enumerate() {
-            println!("[{}] {}", index + 1, item);
-        }
-        let mut buf = String::new();
-
-        print!(">> ");
-        stdout().flush()?;
-
-        stdin().read_line(&mut buf)?;
-
-        let buf = buf.trim().to_lowercase();
-        if buf == "q" {
-            return Err(anyhow!("no test selected!"));
-        }
+/// The most candidates shown when no test matches the requested name exactly.
+const MAX_FUZZY_CANDIDATES: usize = 10;
 
-        let choice: usize = buf.parse()?;
-        if choice < 1 || choice > items.len() - 1 {
-            println!("that is an invalid choice!");
-        }
+/// Asks a yes or no question, anything but `y` or `yes` is taken as no.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    stdout().flush()?;
 
-        return Ok(items.into_iter().skip(choice - 1).next());
-    }
+    let mut buf = String::new();
+    stdin().read_line(&mut buf)?;
+    Ok(matches!(buf.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
 pub fn parse_env_file() -> Result<HashMap<String, String>> {
@@ -71,30 +73,254 @@ pub fn parse_env_file() -> Result<HashMap<String, String>> {
     Ok(map)
 }
 
+#[derive(Clone, Copy)]
 pub enum TestKind {
     Cc,
     Py,
 }
 
+/// What the name given for a test refers to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Selector {
+    /// A single test
+    Test,
+    /// A C++ or python source file, all the tests in it are run
+    File,
+    /// A C++ test binary or a ducktape test class, all the tests in it are run
+    Target,
+    /// A glob or `re:` prefixed regex, all the tests with matching names are run
+    Pattern,
+    /// A `file:line` position, the test whose definition encloses it is run
+    At,
+}
+
+impl Display for TestKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TestKind::Cc => write!(f, "cc"),
+            TestKind::Py => write!(f, "py"),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print everything known about a test without running it
+    Show {
+        #[arg(value_name = "test name")]
+        name: String,
+    },
+    /// Open the output of the latest run, optionally of a given test, in $PAGER
+    Log {
+        #[arg(value_name = "test name")]
+        name: Option<String>,
+    },
+    /// Run the most recently run test again, with the same commands
+    Last {
+        #[arg(long, help = "Run commands with plain stdin/stdout/stderr")]
+        no_color: bool,
+    },
+    /// List recent runs with their outcome and duration
+    History {
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 20,
+            help = "Number of runs to list"
+        )]
+        count: usize,
+    },
+}
+
 #[derive(Parser)]
-#[command(author, version, about)]
-#[clap(group(ArgGroup::new("test-kind").required(true).args(["cc", "py", "cli_content"])))]
+#[command(
+    author,
+    version,
+    about,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
+#[clap(group(ArgGroup::new("test-kind").required(true).multiple(true).args(["cc", "py", "py_class", "py_file", "file", "target", "cc_pattern", "py_pattern", "at", "cli_content"])))]
 pub struct Cli {
-    #[clap(long, value_name = "C++ test name")]
-    cc: Option<String>,
+    #[clap(long, value_name = "C++ test name", num_args = 1..)]
+    cc: Vec<String>,
 
-    #[clap(long, value_name = "Ducktape test name")]
-    py: Option<String>,
+    #[clap(long, value_name = "Ducktape test name", num_args = 1..)]
+    py: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "Ducktape class name",
+        num_args = 1..,
+        help = "Run every test in a ducktape class"
+    )]
+    py_class: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "Ducktape test file",
+        num_args = 1..,
+        help = "Run every test in a ducktape test file"
+    )]
+    py_file: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "source file",
+        num_args = 1..,
+        help = "Run every test in a C++ or python source file"
+    )]
+    file: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "binary or class",
+        num_args = 1..,
+        help = "Run every test in a C++ binary, eg storage_single_thread_rpunit, or a ducktape class"
+    )]
+    target: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "pattern",
+        num_args = 1..,
+        help = "Run every C++ test whose name matches a glob, or a regex prefixed with re:"
+    )]
+    cc_pattern: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "pattern",
+        num_args = 1..,
+        help = "Run every ducktape test whose name matches a glob, or a regex prefixed with re:"
+    )]
+    py_pattern: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "file:line",
+        num_args = 1..,
+        help = "Run the C++ or ducktape test defined around a line of a source file"
+    )]
+    at: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Only run tests with exactly the given names, without offering close matches"
+    )]
+    exact: bool,
 
     #[arg(short, long, help = "Edit command before running test")]
     edit: bool,
 
+    #[arg(
+        long,
+        help = "Print the commands which would be run without running them"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        requires = "dry_run",
+        help = "Print the commands of a dry run as JSON"
+    )]
+    json: bool,
+
+    #[arg(
+        long,
+        help = "Run commands with plain stdin/stdout/stderr, also enabled by setting NO_COLOR"
+    )]
+    no_color: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "until_fail",
+        help = "Compile once and run the test up to N times, stopping at the first failure"
+    )]
+    repeat: Option<u64>,
+
+    #[arg(long, help = "Compile once and run the test until it fails")]
+    until_fail: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_timeout,
+        help = "Kill the test if it runs for longer, eg 90s, 10m or 1h"
+    )]
+    timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "all",
+        help = "When a name matches several tests, run the Nth of them as listed"
+    )]
+    pick: Option<u64>,
+
+    #[arg(
+        long,
+        help = "When a name matches several tests, run all of them, and run the tests matching a pattern without confirming"
+    )]
+    all: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "kind",
+        help = "When a name matches several C++ tests, prefer the tests of this kind"
+    )]
+    prefer_kind: Option<BinaryKind>,
+
+    #[arg(
+        long = "in",
+        value_name = "path prefix",
+        help = "Only consider tests defined under the path, eg src/v/storage"
+    )]
+    in_path: Option<String>,
+
+    #[arg(
+        last = true,
+        value_name = "extra arguments",
+        help = "Arguments passed through to the test, eg `snipe --cc test_foo -- -c2`"
+    )]
+    extra_args: Vec<String>,
+
     #[arg(
         long,
         value_name = "Auto-complete",
+        conflicts_with_all = ["cc", "py", "py_class", "py_file", "file", "target", "cc_pattern", "py_pattern", "at"],
         help = "Provide test names for auto completion"
     )]
     pub cli_content: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Parses a positive duration given in seconds, or with an `s`, `m` or `h` suffix.
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(split) => value.split_at(split),
+        None => (value, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("{value} is not a duration"))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(format!("unknown unit {unit}, use s, m or h")),
+    };
+    match number.checked_mul(multiplier) {
+        Some(0) => Err("the timeout must be longer than zero".to_owned()),
+        Some(seconds) => Ok(Duration::from_secs(seconds)),
+        None => Err(format!("{value} is too long")),
+    }
 }
 
 #[derive(Clone)]
@@ -115,19 +341,130 @@ impl Display for TestSuite {
 }
 
 impl TestSuite {
-    fn matches(&self, name: &str) -> bool {
+    fn location_of(&self, name: &str) -> Option<&Location> {
         match &self {
-            TestSuite::C(t) => t.tests.iter().any(|t| t == name),
-            TestSuite::P(t) => t.tests.iter().any(|t| t == name),
-            TestSuite::None => false,
+            TestSuite::C(t) => t.find_case(name).map(|t| &t.location),
+            TestSuite::P(t) => t.tests.iter().find(|t| t.name == name).map(|t| &t.location),
+            TestSuite::None => None,
+        }
+    }
+
+    fn matches(&self, selector: Selector, name: &str) -> bool {
+        match (selector, &self) {
+            (Selector::Test, TestSuite::C(t)) => t.tests.iter().any(|t| t.matches(name)),
+            (Selector::Test, TestSuite::P(t)) => t.tests.iter().any(|t| t.name == name),
+            (Selector::File, TestSuite::C(t)) => {
+                t.source_paths().iter().any(|path| path.ends_with(name))
+            }
+            (Selector::File, TestSuite::P(t)) => t.source_path.ends_with(name),
+            (Selector::Target, TestSuite::C(t)) => t.binary_name() == name || t.name == name,
+            (Selector::Target, TestSuite::P(t)) => t.class_name == name,
+            // patterns and positions are matched against each test, see `resolve_pattern` and
+            // `resolve_at`
+            (Selector::Pattern | Selector::At, _) | (_, TestSuite::None) => false,
+        }
+    }
+}
+
+/// A test suite matching the requested name, along with where the matching test is defined.
+struct Candidate {
+    suite: TestSuite,
+    name: String,
+    /// The test name with the matched chars highlighted, for a fuzzy match
+    label: Option<String>,
+    location: Option<Location>,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "{label} in ")?;
+        }
+        match &self.location {
+            Some(location) => write!(f, "{} at {}", self.suite, location),
+            None => write!(f, "{}", self.suite),
+        }
+    }
+}
+
+impl Pick for Candidate {
+    fn title(&self) -> String {
+        format!("{} in {}", self.name, self.suite)
+    }
+
+    fn preview(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match &self.suite {
+            TestSuite::C(test) => {
+                lines.push(format_field("binary", test.binary_name()));
+                lines.push(format_field("kind", &test.kind));
+                if let Some(case) = test.find_case(&self.name) {
+                    lines.push(format_field("framework", &case.framework));
+                }
+                lines.push(format_field(
+                    "cmake file",
+                    test.cmake_file.to_string_lossy(),
+                ));
+            }
+            TestSuite::P(test) => {
+                lines.push(format_field("class", &test.class_name));
+                lines.push(format_field("kind", "ducktape"));
+                lines.push(format_field("source", test.source_path.to_string_lossy()));
+            }
+            TestSuite::None => {}
+        }
+        if let Some(location) = &self.location {
+            lines.push(format_field("defined at", location));
+        }
+        lines
+    }
+}
+
+/// Narrows down the tests matching a name without asking which to run.
+#[derive(Clone, Default)]
+struct Disambiguation {
+    /// Runs the Nth of the matching tests, counting from one
+    pick: Option<usize>,
+    /// Runs every matching test
+    all: bool,
+    /// Keeps only the C++ tests of this kind, if any matches
+    prefer_kind: Option<BinaryKind>,
+    /// Only considers tests defined under this path
+    path_prefix: Option<String>,
+}
+
+impl Disambiguation {
+    fn is_under_path(&self, suite: &TestSuite, name: &str) -> bool {
+        let Some(prefix) = &self.path_prefix else {
+            return true;
+        };
+        let prefix = prefix.trim_start_matches("./");
+        let under = |path: &Path| path.to_string_lossy().starts_with(prefix);
+        match (suite.location_of(name), suite) {
+            (Some(location), _) => under(&location.file),
+            (None, TestSuite::C(test)) => test.source_paths().iter().any(|path| under(path)),
+            (None, TestSuite::P(test)) => under(&test.source_path),
+            (None, TestSuite::None) => false,
+        }
+    }
+
+    fn is_preferred_kind(&self, suite: &TestSuite) -> bool {
+        match (&self.prefer_kind, suite) {
+            (Some(kind), TestSuite::C(test)) => test.kind == *kind,
+            _ => false,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct SearchAndExecute {
     kind: TestKind,
+    selector: Selector,
     name: String,
-    edit: bool,
+    /// Disables falling back to fuzzy matching when no test has the requested name
+    exact: bool,
+    disambiguation: Disambiguation,
+    options: RunOptions,
     scan_config: ScanConfig,
     command_config: CommandRunConfig,
     command_environment: CommandEnv,
@@ -140,7 +477,32 @@ fn get_db_file(kind: &TestKind) -> &'static str {
     }
 }
 
+fn format_field(label: &str, value: impl Display) -> String {
+    format!("{:<13}{}", format!("{label}:"), value)
+}
+
+fn print_field(label: &str, value: impl Display) {
+    println!("  {}", format_field(label, value));
+}
+
 impl SearchAndExecute {
+    fn new(kind: TestKind, name: String, options: RunOptions) -> Self {
+        let scan_config = load_configuration(None).expect("Failed to load scan config");
+        let command_config = load_configuration(None).expect("Failed to load command config");
+        let command_environment = load_configuration(None).expect("Failed to load command envs");
+        Self {
+            kind,
+            selector: Selector::Test,
+            name,
+            exact: false,
+            disambiguation: Disambiguation::default(),
+            options,
+            scan_config,
+            command_config,
+            command_environment,
+        }
+    }
+
     fn file_name(&self) -> &str {
         get_db_file(&self.kind)
     }
@@ -148,7 +510,7 @@ impl SearchAndExecute {
     fn scan_and_store_definitions(&self) -> Result<()> {
         let tests_json = match self.kind {
             TestKind::Cc => {
-                let tests = cmake::collect_cmake_test_definitions(&self.scan_config.cc_test_root)?;
+                let tests = cmake::collect_cmake_test_definitions(&self.scan_config)?;
                 serde_json::to_string_pretty(&tests)?
             }
             TestKind::Py => {
@@ -164,6 +526,17 @@ impl SearchAndExecute {
     }
 
     fn load_tests_from_db(&self) -> Result<Vec<TestSuite>> {
+        match self.read_tests_from_db() {
+            Ok(tests) => Ok(tests),
+            Err(err) => {
+                eprintln!("failed to read cached tests ({err}), rescanning...");
+                self.scan_and_store_definitions()?;
+                self.read_tests_from_db()
+            }
+        }
+    }
+
+    fn read_tests_from_db(&self) -> Result<Vec<TestSuite>> {
         let db = get_data_file_handle(self.file_name())?.expect("unexpected missing data file");
         let tests = match self.kind {
             TestKind::Cc => {
@@ -182,7 +555,7 @@ impl SearchAndExecute {
         let tests = self.load_tests_from_db()?;
         let mut matching = Vec::new();
         for test in tests {
-            if test.matches(&self.name) {
+            if test.matches(self.selector, &self.name) {
                 matching.push(test.clone());
             }
         }
@@ -198,55 +571,517 @@ impl SearchAndExecute {
         Ok(())
     }
 
-    pub fn find_test(&self) -> Result<TestSuite> {
-        let mut matching = self.find_matching_tests()?;
-        if matching.is_empty() {
-            println!("test not found in cache, rescanning...");
-            self.scan_and_store_definitions()?;
-            matching = self.find_matching_tests()?;
-            if matching.is_empty() {
-                return Ok(TestSuite::None);
+    /// Finds the matching tests, rescanning the sources if none are found in the cache.
+    fn find_matching_tests_or_rescan(&self) -> Result<Vec<TestSuite>> {
+        let matching = self.find_matching_tests()?;
+        if !matching.is_empty() {
+            return Ok(matching);
+        }
+
+        eprintln!("test not found in cache, rescanning...");
+        self.scan_and_store_definitions()?;
+        self.find_matching_tests()
+    }
+
+    /// Finds the suites to run for the name. Several matching suites are narrowed down by the
+    /// disambiguation options, and if more than one is left without `--all` or `--pick`, one is
+    /// picked from a list.
+    /// A leaf name matching cases of several suites in one binary counts as several matches, each
+    /// named by the case's qualified name. Returns the suites along with the name of their test.
+    pub fn find_tests(&self) -> Result<Vec<(TestSuite, String)>> {
+        let mut matching = Vec::new();
+        for suite in self.find_matching_tests_or_rescan()? {
+            let names = match (&suite, self.selector) {
+                (TestSuite::C(test), Selector::Test) => test.find_cases(&self.name),
+                _ => Vec::new(),
+            };
+            if names.len() > 1 {
+                for case in names {
+                    matching.push((suite.clone(), case.qualified_name()));
+                }
+            } else {
+                matching.push((suite, self.name.clone()));
             }
         }
+        self.choose(matching)
+    }
 
-        if matching.len() == 1 {
-            Ok(matching.into_iter().next().unwrap())
-        } else {
-            match select_from_list(matching, &self.name)? {
-                None => Ok(TestSuite::None),
-                Some(t) => Ok(t),
+    /// Narrows down the suites matching the search, each along with the name of its test, by the
+    /// disambiguation options. If more than one is left without `--all` or `--pick`, one is picked
+    /// from a list.
+    fn choose(&self, mut matching: Vec<(TestSuite, String)>) -> Result<Vec<(TestSuite, String)>> {
+        let disambiguation = &self.disambiguation;
+        matching.retain(|(suite, name)| disambiguation.is_under_path(suite, name));
+        if matching.len() > 1
+            && matching
+                .iter()
+                .any(|(suite, _)| disambiguation.is_preferred_kind(suite))
+        {
+            matching.retain(|(suite, _)| disambiguation.is_preferred_kind(suite));
+        }
+        if matching.len() <= 1 || disambiguation.all {
+            return Ok(matching);
+        }
+
+        if let Some(pick) = disambiguation.pick {
+            let count = matching.len();
+            return match matching.into_iter().nth(pick - 1) {
+                Some(found) => Ok(vec![found]),
+                None => Err(anyhow!(
+                    "cannot pick match {pick}, only {count} tests match {}",
+                    self.name
+                )),
+            };
+        }
+
+        let candidates = matching
+            .into_iter()
+            .map(|(suite, name)| Candidate {
+                location: suite.location_of(&name).cloned(),
+                // a file is named by its suite already
+                label: (name != self.name && self.selector != Selector::File).then(|| name.clone()),
+                name,
+                suite,
+            })
+            .collect();
+        let header = format!("Multiple matches found for {}", self.name);
+        let hint = "pass --pick N, --all, --prefer-kind or --in to choose";
+        Ok(select_from_list(candidates, &header, hint)?
+            .map(|c| (c.suite, c.name))
+            .into_iter()
+            .collect())
+    }
+
+    /// Searches for another test with the same options and configuration.
+    fn search_for(&self, kind: TestKind, selector: Selector, name: String) -> Self {
+        Self {
+            kind,
+            selector,
+            name,
+            ..self.clone()
+        }
+    }
+
+    /// Finds the suites to run for the search, along with the tests to run from each. Returns an
+    /// empty list if nothing matches.
+    /// If no test has the requested name, the closest names are offered instead, and the search
+    /// is updated with the chosen one.
+    fn resolve(&mut self) -> Result<Vec<(TestSuite, Selection)>> {
+        let mut suites = match self.selector {
+            Selector::File => return self.resolve_file(),
+            Selector::Pattern => return self.resolve_pattern(),
+            Selector::At => return self.resolve_at(),
+            _ => self.find_tests()?,
+        };
+        if suites.is_empty() && self.selector == Selector::Test && !self.exact {
+            match self.find_test_fuzzy()? {
+                Some((suite, name)) => {
+                    self.name = name.clone();
+                    suites.push((suite, name));
+                }
+                None => return Ok(Vec::new()),
+            }
+        }
+        Ok(suites
+            .into_iter()
+            .map(|(suite, name)| {
+                let selection = match self.selector {
+                    Selector::Target => Selection::All,
+                    _ => Selection::Tests(vec![name]),
+                };
+                (suite, selection)
+            })
+            .collect())
+    }
+
+    /// Ranks every cached test by how well its name fuzzy matches the requested one, and offers
+    /// the best matches to pick from. Returns the picked test's suite and name.
+    fn find_test_fuzzy(&self) -> Result<Option<(TestSuite, String)>> {
+        let suites = self.load_tests_from_db()?;
+        let mut ranked = Vec::new();
+        for (index, suite) in suites.iter().enumerate() {
+            let names: Vec<String> = match suite {
+                TestSuite::C(t) => t.tests.iter().map(|case| case.qualified_name()).collect(),
+                TestSuite::P(t) => t.tests.iter().map(|test| test.name.clone()).collect(),
+                TestSuite::None => Vec::new(),
+            };
+            for name in names {
+                if !self.disambiguation.is_under_path(suite, &name) {
+                    continue;
+                }
+                if let Some(found) = fuzzy_match(&self.name, &name) {
+                    ranked.push((found, name, index));
+                }
+            }
+        }
+        if ranked.is_empty() {
+            return Ok(None);
+        }
+
+        ranked.sort_by(|(a, a_name, _), (b, b_name, _)| {
+            b.score.cmp(&a.score).then(a_name.len().cmp(&b_name.len()))
+        });
+        ranked.truncate(MAX_FUZZY_CANDIDATES);
+        let candidates = ranked
+            .into_iter()
+            .map(|(found, name, index)| {
+                let suite = suites[index].clone();
+                Candidate {
+                    location: suite.location_of(&name).cloned(),
+                    label: Some(highlight(&name, &found.positions)),
+                    name,
+                    suite,
+                }
+            })
+            .collect();
+        let header = format!("No test named {}, closest matches:", self.name);
+        let hint = "pass the full name of the test";
+        Ok(select_from_list(candidates, &header, hint)?.map(|c| (c.suite, c.name)))
+    }
+
+    /// A C++ source file is run by selecting the tests defined in it from each binary it is built
+    /// into, while a python file is passed to ducktape whole. Several python files matching the
+    /// name are chosen from like several tests matching a name.
+    fn resolve_file(&mut self) -> Result<Vec<(TestSuite, Selection)>> {
+        self.name = relative_to_root(&self.name).to_string_lossy().into_owned();
+        let mut resolved = Vec::new();
+        let mut py_files: Vec<(TestSuite, String)> = Vec::new();
+        for suite in self.find_matching_tests_or_rescan()? {
+            match &suite {
+                TestSuite::C(test) => {
+                    // one selection per test, they are batched by the kind of filter they need
+                    resolved.extend(
+                        test.tests
+                            .iter()
+                            .filter(|case| case.location.file.ends_with(&self.name))
+                            .map(|case| {
+                                (suite.clone(), Selection::Tests(vec![case.qualified_name()]))
+                            }),
+                    );
+                }
+                TestSuite::P(class) => {
+                    // any class of the file stands for the whole file
+                    let path = class.source_path.to_string_lossy().into_owned();
+                    if !py_files.iter().any(|(_, known)| *known == path) {
+                        py_files.push((suite, path));
+                    }
+                }
+                TestSuite::None => {}
+            }
+        }
+        if !py_files.is_empty() {
+            py_files.sort_by(|(_, a), (_, b)| a.cmp(b));
+            resolved.extend(
+                self.choose(py_files)?
+                    .into_iter()
+                    .map(|(suite, _)| (suite, Selection::File)),
+            );
+        }
+        Ok(resolved)
+    }
+
+    /// Selects every cached test whose name matches the pattern, rescanning the sources if none
+    /// does. A C++ test matches by its leaf or qualified name.
+    fn resolve_pattern(&self) -> Result<Vec<(TestSuite, Selection)>> {
+        let pattern = NamePattern::parse(&self.name)?;
+        let resolved = select_by_pattern(self.load_tests_from_db()?, &pattern);
+        if !resolved.is_empty() {
+            return Ok(resolved);
+        }
+
+        eprintln!("no test matching {} in cache, rescanning...", self.name);
+        self.scan_and_store_definitions()?;
+        Ok(select_by_pattern(self.load_tests_from_db()?, &pattern))
+    }
+
+    /// Scans the source file for the test whose definition encloses the line, so that positions
+    /// in files edited since the last scan are found. A C++ test is selected from each binary the
+    /// file is built into. The search is updated with the name of the test.
+    fn resolve_at(&mut self) -> Result<Vec<(TestSuite, Selection)>> {
+        let (path, line) = parse_position(&self.name)?;
+        match self.kind {
+            TestKind::Cc => {
+                let cases = cmake::find_tests_in_cc_source(&path, &self.scan_config)
+                    .with_context(|| format!("cannot read {path:?}"))?;
+                let Some(case) = cases
+                    .into_iter()
+                    .filter(|case| case.location.encloses(line))
+                    .max_by_key(|case| case.location.line)
+                else {
+                    return Ok(Vec::new());
+                };
+
+                let name = case.qualified_name();
+                let file = self.search_for(
+                    TestKind::Cc,
+                    Selector::File,
+                    path.to_string_lossy().into_owned(),
+                );
+                let has_case = |suite: &TestSuite| match suite {
+                    TestSuite::C(test) => test.find_case(&name).is_some(),
+                    _ => false,
+                };
+                // a single rescan finds both a file and a test missing from the cache
+                let mut suites = file.find_matching_tests()?;
+                if !suites.iter().any(has_case) {
+                    eprintln!("test not found in cache, rescanning...");
+                    file.scan_and_store_definitions()?;
+                    suites = file.find_matching_tests()?;
+                }
+                let resolved = suites
+                    .into_iter()
+                    .filter(has_case)
+                    .map(|suite| (suite, Selection::Tests(vec![name.clone()])))
+                    .collect();
+                self.name = name;
+                Ok(resolved)
+            }
+            TestKind::Py => {
+                let classes = py_parser::find_tests_in_source(&path)
+                    .with_context(|| format!("cannot read {path:?}"))?;
+                for class in classes {
+                    let found = class.tests.iter().find(|test| test.location.encloses(line));
+                    if let Some(test) = found {
+                        self.name = test.name.clone();
+                        let selection = Selection::Tests(vec![self.name.clone()]);
+                        return Ok(vec![(TestSuite::P(class), selection)]);
+                    }
+                }
+                Ok(Vec::new())
             }
         }
     }
 
-    pub fn run_test(&self, f: TestSuite) -> Result<()> {
+    /// Runs the selected tests from the suite and returns the exit code. Compile steps which
+    /// already ran in the batch are not run again.
+    pub fn run_test(
+        &self,
+        f: TestSuite,
+        selection: Selection,
+        batch: &mut BatchState,
+    ) -> Result<i32> {
         match f {
             TestSuite::C(test) => run_cc_test(
                 test,
-                self.name.clone(),
-                self.edit,
+                selection,
+                &self.options,
                 &self.command_config,
                 &self.command_environment.envs,
+                batch,
             ),
             TestSuite::P(test) => run_py_test(
                 test,
-                self.name.clone(),
-                self.edit,
+                selection,
+                &self.options,
                 &self.command_config,
                 &self.command_environment.envs,
+                batch,
             ),
             TestSuite::None => {
                 println!("no test found");
-                Ok(())
+                Ok(1)
+            }
+        }
+    }
+
+    /// Prints what snipe knows about every test matching `name`, in both the C++ and the python
+    /// test caches, including the commands which would be run for it.
+    pub fn show(name: &str) -> Result<()> {
+        let searches = [TestKind::Cc, TestKind::Py]
+            .map(|kind| Self::new(kind, name.to_owned(), RunOptions::default()));
+        let mut found = Vec::new();
+        for search in &searches {
+            search.ensure_db_exists()?;
+            found.push(search.find_matching_tests()?);
+        }
+
+        if found.iter().all(Vec::is_empty) {
+            eprintln!("test not found in cache, rescanning...");
+            found.clear();
+            for search in &searches {
+                search.scan_and_store_definitions()?;
+                found.push(search.find_matching_tests()?);
+            }
+        }
+
+        if found.iter().all(Vec::is_empty) {
+            println!("no test found");
+            return Ok(());
+        }
+
+        for (search, matching) in searches.iter().zip(found) {
+            for suite in matching {
+                let cases = match &suite {
+                    TestSuite::C(test) => test.find_cases(name),
+                    _ => Vec::new(),
+                };
+                if cases.len() > 1 {
+                    for case in cases {
+                        search.describe(&suite, &case.qualified_name())?;
+                    }
+                } else {
+                    search.describe(&suite, name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self, suite: &TestSuite, name: &str) -> Result<()> {
+        let commands = match suite {
+            TestSuite::C(test) => {
+                println!("C++ test {name}");
+                print_field("binary", test.binary_name());
+                print_field("kind", &test.kind);
+                print_field("cmake file", test.cmake_file.to_string_lossy());
+                for source in test.source_paths() {
+                    print_field("source", source.to_string_lossy());
+                }
+                if let Some(case) = test.find_case(name) {
+                    print_field(
+                        "test",
+                        format!("{} ({})", case.qualified_name(), case.framework),
+                    );
+                    print_field("defined at", &case.location);
+                }
+                build_cc_command(
+                    test.clone(),
+                    &Selection::Tests(vec![name.to_owned()]),
+                    &self.command_config,
+                    &self.options.extra_args,
+                )?
             }
+            TestSuite::P(test) => {
+                println!("ducktape test {name}");
+                print_field("class", &test.class_name);
+                print_field("source", test.source_path.to_string_lossy());
+                if let Some(location) = suite.location_of(name) {
+                    print_field("defined at", location);
+                }
+                build_py_command(
+                    test.clone(),
+                    &Selection::Tests(vec![name.to_owned()]),
+                    &self.command_config,
+                    &self.options.extra_args,
+                    1,
+                )?
+            }
+            TestSuite::None => return Ok(()),
+        };
+
+        for command in commands {
+            print_field(&command.template, command.command);
+        }
+
+        let mut envs: Vec<_> = self.command_environment.envs.iter().collect();
+        envs.sort();
+        println!("  environment:");
+        for (key, value) in envs {
+            println!("    {key}={value}");
+        }
+        println!();
+        Ok(())
+    }
+
+    fn select_runner(&mut self, no_color: bool) {
+        let no_color = no_color
+            || env::var_os("NO_COLOR")
+                .map(|v| !v.is_empty())
+                .unwrap_or(false);
+        self.options.runner = if no_color {
+            Runner::Plain
+        } else {
+            self.command_config.runner
+        };
+    }
+
+    /// Runs the commands recorded for the most recent run again, skipping the lookup and
+    /// selection of the test. Returns the exit code of the run.
+    pub fn rerun_last(no_color: bool) -> Result<i32> {
+        let Some(record) = history::load_history()?.pop() else {
+            println!("no runs recorded");
+            return Ok(1);
+        };
+
+        let kind = match record.target {
+            RunTarget::Binary { .. } => TestKind::Cc,
+            RunTarget::Class { .. } => TestKind::Py,
+        };
+        let mut context = Self::new(kind, record.test_name.clone(), RunOptions::default());
+        context.select_runner(no_color);
+        eprintln!("rerunning {} from {}", record.test_name, record.target);
+        rerun(record, &context.options, &context.command_environment.envs)
+    }
+
+    /// Prints the most recent `count` runs, oldest first.
+    pub fn print_history(count: usize) -> Result<()> {
+        let history = history::load_history()?;
+        if history.is_empty() {
+            println!("no runs recorded");
+            return Ok(());
+        }
+
+        for record in &history[history.len().saturating_sub(count)..] {
+            let outcome = if record.passed() {
+                "PASS".to_owned()
+            } else if record.timed_out() {
+                "TIMEOUT".to_owned()
+            } else {
+                format!("FAIL ({})", record.exit_code)
+            };
+            let kind = match record.target {
+                RunTarget::Binary { .. } => "cc",
+                RunTarget::Class { .. } => "py",
+            };
+            println!(
+                "{}  {:<10} {:>9.1}s  {kind}  {}  {}",
+                record.started_at.format("%Y-%m-%d %H:%M:%S"),
+                outcome,
+                record.duration_ms as f64 / 1000.0,
+                record.test_name,
+                record.target,
+            );
         }
+        Ok(())
+    }
+
+    /// Opens the log of the most recent run in `$PAGER`, or `less -R` if it is not set. The run of
+    /// a given test is looked up in the history, whose records name the tests of each run.
+    pub fn open_log(name: Option<&str>) -> Result<()> {
+        let log = match name {
+            None => run_log::latest_log()?,
+            Some(name) => history::load_history()?
+                .into_iter()
+                .rev()
+                // older logs are pruned before their records
+                .find(|record| record.is_run_of(name) && record.log.exists())
+                .map(|record| record.log),
+        };
+        let Some(log) = log else {
+            println!("no run logs found");
+            return Ok(());
+        };
+
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less -R".to_owned());
+        let pager = shell_words::split(&pager)?;
+        let (program, args) = pager
+            .split_first()
+            .ok_or_else(|| anyhow!("PAGER is empty"))?;
+        eprintln!("opening {}", log.to_string_lossy());
+        process::Command::new(program)
+            .args(args)
+            .arg(log)
+            .status()?;
+        Ok(())
     }
 
     fn do_autocomplete(command_line: &str) -> Result<Vec<String>> {
         let tokens: Vec<_> = command_line.split(",").collect();
+        // the completion function strips dashes, so `--py-class` arrives as `pyclass`
+        let complete_classes = tokens.contains(&"pyclass");
         let kind = if tokens.iter().any(|token| *token == "cc") {
             Some(TestKind::Cc)
-        } else if tokens.iter().any(|token| *token == "py") {
+        } else if complete_classes || tokens.iter().any(|token| *token == "py") {
             Some(TestKind::Py)
         } else {
             return Err(anyhow!("no test kind"));
@@ -258,8 +1093,11 @@ impl SearchAndExecute {
 
         let sar = Self {
             kind: kind.unwrap(),
+            selector: Selector::Test,
             name: "".to_owned(),
-            edit: false,
+            exact: false,
+            disambiguation: Disambiguation::default(),
+            options: RunOptions::default(),
             scan_config,
             command_config,
             command_environment,
@@ -270,8 +1108,11 @@ impl SearchAndExecute {
         let mut test_names = vec![];
         for test_suite in tests {
             match test_suite {
-                TestSuite::C(rp_test) => test_names.extend(rp_test.tests),
-                TestSuite::P(py) => test_names.extend(py.tests),
+                TestSuite::C(rp_test) => {
+                    test_names.extend(rp_test.tests.into_iter().map(|t| t.name))
+                }
+                TestSuite::P(py) if complete_classes => test_names.push(py.class_name),
+                TestSuite::P(py) => test_names.extend(py.tests.into_iter().map(|t| t.name)),
                 TestSuite::None => {}
             }
         }
@@ -285,25 +1126,349 @@ impl SearchAndExecute {
     }
 }
 
-impl From<Cli> for SearchAndExecute {
+/// One selection per matching test, so that they can be batched like tests requested by name.
+fn select_by_pattern(suites: Vec<TestSuite>, pattern: &NamePattern) -> Vec<(TestSuite, Selection)> {
+    let mut selected = Vec::new();
+    for suite in suites {
+        let names: Vec<String> = match &suite {
+            TestSuite::C(test) => test
+                .tests
+                .iter()
+                .filter(|case| {
+                    pattern.matches(&case.name) || pattern.matches(&case.qualified_name())
+                })
+                .map(|case| case.qualified_name())
+                .collect(),
+            TestSuite::P(test) => test
+                .tests
+                .iter()
+                .filter(|test| pattern.matches(&test.name))
+                .map(|test| test.name.clone())
+                .collect(),
+            TestSuite::None => Vec::new(),
+        };
+        selected.extend(
+            names
+                .into_iter()
+                .map(|name| (suite.clone(), Selection::Tests(vec![name]))),
+        );
+    }
+    selected
+}
+
+/// A run of the selected tests from one suite, for one or more of the requested tests.
+struct PlannedRun {
+    search: SearchAndExecute,
+    suite: TestSuite,
+    selection: Selection,
+    /// The names the tests were requested by, for the summary
+    requested_as: Vec<String>,
+}
+
+/// Merges the named C++ tests which resolved to the same binary, and are run with the same
+/// template and kind of filter, so that the binary is launched once with a filter selecting all of
+/// them. Named ducktape tests from the same class are merged into a single ducktape run.
+fn batch_by_binary(
+    resolved: Vec<(SearchAndExecute, TestSuite, Selection)>,
+    command_config: &CommandRunConfig,
+) -> Vec<PlannedRun> {
+    let mut batches: Vec<(Option<_>, PlannedRun)> = Vec::new();
+    for (search, suite, selection) in resolved {
+        let key = match (&suite, &selection) {
+            (TestSuite::C(test), Selection::Tests(names)) => names
+                .first()
+                .and_then(|name| test.find_case(name))
+                .map(|case| {
+                    (
+                        test.binary_name(),
+                        run_template_for(command_config, &case.framework),
+                        case.framework == TestFramework::GTest,
+                    )
+                }),
+            (TestSuite::P(test), Selection::Tests(_)) => {
+                Some((test.to_string(), "duck".to_owned(), false))
+            }
+            _ => None,
+        };
+        let batch = batches
+            .iter_mut()
+            .find(|(batch_key, _)| key.is_some() && *batch_key == key);
+        match (batch, selection) {
+            (Some((_, run)), Selection::Tests(more)) => {
+                match (&run.suite, &mut run.selection) {
+                    (TestSuite::C(test), Selection::Tests(names)) => {
+                        for name in more {
+                            // the same test may be requested by its leaf and qualified names
+                            let case = test.find_case(&name);
+                            if !names.iter().any(|n| test.find_case(n) == case) {
+                                names.push(name);
+                            }
+                        }
+                    }
+                    (_, Selection::Tests(names)) => {
+                        for name in more {
+                            if !names.contains(&name) {
+                                names.push(name);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if !run.requested_as.contains(&search.name) {
+                    run.requested_as.push(search.name);
+                }
+            }
+            (_, selection) => batches.push((
+                key,
+                PlannedRun {
+                    requested_as: vec![search.name.clone()],
+                    search,
+                    suite,
+                    selection,
+                },
+            )),
+        }
+    }
+    batches.into_iter().map(|(_, run)| run).collect()
+}
+
+/// All the tests requested on the command line. The tests are looked up before any of them is run,
+/// then run one after the other.
+pub struct TestBatch {
+    searches: Vec<SearchAndExecute>,
+}
+
+impl TestBatch {
+    /// Runs every test and returns the exit code of the first one which failed.
+    pub fn run(&self) -> Result<i32> {
+        let mut resolved = Vec::with_capacity(self.searches.len());
+        for search in &self.searches {
+            search.ensure_db_exists()?;
+            let mut search = search.clone();
+            if search.selector == Selector::Target
+                && matches!(search.kind, TestKind::Cc)
+                && search.find_matching_tests()?.is_empty()
+            {
+                // look for a ducktape class in the cache before rescanning the C++ tree for a binary
+                let class = search.search_for(TestKind::Py, Selector::Target, search.name.clone());
+                class.ensure_db_exists()?;
+                if !class.find_matching_tests()?.is_empty() {
+                    search = class;
+                }
+            }
+            let mut found = search.resolve()?;
+            if found.is_empty() && search.selector == Selector::Target {
+                // the target was not a C++ binary, it may be a ducktape class
+                search = search.search_for(TestKind::Py, Selector::Target, search.name.clone());
+                search.ensure_db_exists()?;
+                found = search.resolve()?;
+            }
+            if found.is_empty() {
+                println!("no test found for {}", search.name);
+                return Ok(1);
+            }
+            resolved.extend(found.into_iter().map(|(suite, selection)| {
+                let search = match &selection {
+                    // each test matching a pattern is listed under its own name in the summary
+                    Selection::Tests(names) if search.selector == Selector::Pattern => {
+                        search.search_for(search.kind, Selector::Test, names.join(","))
+                    }
+                    _ => search.clone(),
+                };
+                (search, suite, selection)
+            }));
+        }
+
+        let batches = batch_by_binary(resolved, &self.searches[0].command_config);
+        let has_pattern = self
+            .searches
+            .iter()
+            .any(|search| search.selector == Selector::Pattern);
+        let first = &self.searches[0];
+        if has_pattern
+            && first.options.dry_run == DryRun::Off
+            && !first.disambiguation.all
+            && !confirm_batches(&batches)?
+        {
+            eprintln!("no tests run");
+            return Ok(1);
+        }
+
+        let mut state = BatchState::default();
+        let mut outcomes = Vec::with_capacity(batches.len());
+        for run in batches {
+            let code = run.search.run_test(run.suite, run.selection, &mut state)?;
+            let kind = run.search.kind;
+            outcomes.extend(run.requested_as.into_iter().map(|name| (name, kind, code)));
+        }
+
+        if self.searches[0].options.dry_run == DryRun::Json {
+            println!("{}", serde_json::to_string_pretty(&state.dry_run_commands)?);
+        }
+
+        if outcomes.len() > 1 && self.searches[0].options.dry_run == DryRun::Off {
+            let width = outcomes
+                .iter()
+                .map(|(name, ..)| name.len())
+                .max()
+                .unwrap_or(0);
+            println!();
+            for (name, kind, code) in &outcomes {
+                let outcome = match code {
+                    0 => "PASS".to_owned(),
+                    code => format!("FAIL ({code})"),
+                };
+                println!("{name:<width$}  {kind}  {outcome}");
+            }
+        }
+
+        Ok(outcomes
+            .iter()
+            .map(|(.., code)| *code)
+            .find(|code| *code != 0)
+            .unwrap_or(0))
+    }
+}
+
+/// Lists the tests to run, grouped by the binary or class they are run from, and asks whether to
+/// run them. If stdin is not interactive nothing is asked, and the error lists the tests.
+fn confirm_batches(batches: &[PlannedRun]) -> Result<bool> {
+    let count: usize = batches.iter().map(|run| run.requested_as.len()).sum();
+    let mut listing = format!("{count} tests selected:");
+    for run in batches {
+        listing.push_str(&format!(
+            "\n  {}: {}",
+            run.suite,
+            run.requested_as.join(", ")
+        ));
+    }
+    if !stdin().is_terminal() {
+        return Err(anyhow!(
+            "{listing}\nstdin is not interactive, pass --all to run them without confirming"
+        ));
+    }
+
+    println!("{listing}");
+    confirm("Run them?")
+}
+
+/// Splits a `file:line` position, see [`relative_to_root`] for the path.
+fn parse_position(position: &str) -> Result<(PathBuf, usize)> {
+    let invalid = || anyhow!("expected a position as file:line, got {position}");
+    let (path, line) = position.rsplit_once(':').ok_or_else(invalid)?;
+    let line = line.parse().map_err(|_| invalid())?;
+    Ok((relative_to_root(path), line))
+}
+
+/// Strips a leading `./` from the path, and makes an absolute path relative to the project root,
+/// which is the working directory.
+fn relative_to_root(path: &str) -> PathBuf {
+    let path = Path::new(path.trim_start_matches("./"));
+    let path = match env::current_dir() {
+        Ok(root) => path.strip_prefix(root).unwrap_or(path),
+        Err(_) => path,
+    };
+    path.to_owned()
+}
+
+/// The kind of test a source file holds, going by its extension.
+fn kind_of_file(path: &str) -> TestKind {
+    if path.ends_with(".py") {
+        TestKind::Py
+    } else {
+        TestKind::Cc
+    }
+}
+
+impl From<Cli> for TestBatch {
     fn from(value: Cli) -> Self {
-        let (kind, name) = if let Some(cc) = value.cc {
-            (TestKind::Cc, cc)
-        } else if let Some(py) = value.py {
-            (TestKind::Py, py)
-        } else {
+        let names: Vec<_> = value
+            .cc
+            .into_iter()
+            .map(|name| (TestKind::Cc, Selector::Test, name))
+            .chain(
+                value
+                    .py
+                    .into_iter()
+                    .map(|name| (TestKind::Py, Selector::Test, name)),
+            )
+            .chain(
+                value
+                    .py_class
+                    .into_iter()
+                    .map(|name| (TestKind::Py, Selector::Target, name)),
+            )
+            .chain(
+                value
+                    .py_file
+                    .into_iter()
+                    .map(|path| (TestKind::Py, Selector::File, path)),
+            )
+            .chain(
+                value
+                    .file
+                    .into_iter()
+                    .map(|path| (kind_of_file(&path), Selector::File, path)),
+            )
+            .chain(
+                value
+                    .target
+                    .into_iter()
+                    .map(|name| (TestKind::Cc, Selector::Target, name)),
+            )
+            .chain(value.at.into_iter().map(|position| {
+                let path = position
+                    .rsplit_once(':')
+                    .map_or(position.as_str(), |(path, _)| path);
+                (kind_of_file(path), Selector::At, position)
+            }))
+            .chain(
+                value
+                    .cc_pattern
+                    .into_iter()
+                    .map(|pattern| (TestKind::Cc, Selector::Pattern, pattern)),
+            )
+            .chain(
+                value
+                    .py_pattern
+                    .into_iter()
+                    .map(|pattern| (TestKind::Py, Selector::Pattern, pattern)),
+            )
+            .collect();
+        let Some((kind, _, name)) = names.first().cloned() else {
             panic!("unexpected run config")
         };
-        let scan_config = load_configuration(None).expect("Failed to load scan config");
-        let command_config = load_configuration(None).expect("Failed to load command config");
-        let command_environment = load_configuration(None).expect("Failed to load command envs");
-        Self {
-            kind,
-            name,
+        let dry_run = match (value.dry_run, value.json) {
+            (false, _) => DryRun::Off,
+            (true, false) => DryRun::Shell,
+            (true, true) => DryRun::Json,
+        };
+        let repeat = match (value.repeat, value.until_fail) {
+            (Some(n), _) => Repeat::Times(n),
+            (None, true) => Repeat::UntilFail,
+            (None, false) => Repeat::Once,
+        };
+        let options = RunOptions {
             edit: value.edit,
-            scan_config,
-            command_config,
-            command_environment,
-        }
+            dry_run,
+            extra_args: value.extra_args,
+            repeat,
+            timeout: value.timeout,
+            ..Default::default()
+        };
+        let mut first = SearchAndExecute::new(kind, name, options);
+        first.select_runner(value.no_color);
+        first.exact = value.exact;
+        first.disambiguation = Disambiguation {
+            pick: value.pick.map(|pick| pick as usize),
+            all: value.all,
+            prefer_kind: value.prefer_kind,
+            path_prefix: value.in_path,
+        };
+        let searches = names
+            .into_iter()
+            .map(|(kind, selector, name)| first.search_for(kind, selector, name))
+            .collect();
+        Self { searches }
     }
 }
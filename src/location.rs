@@ -0,0 +1,35 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Where a test is defined. Line and column are one based, as reported by compilers and editors.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// The last line of the definition, zero if not known
+    #[serde(default)]
+    pub end_line: usize,
+}
+
+impl Location {
+    /// Whether the line is part of the definition.
+    pub fn encloses(&self, line: usize) -> bool {
+        self.line <= line && line <= self.end_line.max(self.line)
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file.to_string_lossy(),
+            self.line,
+            self.column
+        )
+    }
+}
@@ -1,16 +1,29 @@
 use anyhow::Result;
 use clap::Parser;
 
-use snipe::{Cli, SearchAndExecute};
+use snipe::{Cli, Command, SearchAndExecute, TestBatch};
+
+fn exit_with(code: i32) -> Result<()> {
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    if let Some(command_line) = cli.cli_content {
+    if let Some(Command::Show { name }) = cli.command {
+        SearchAndExecute::show(&name)
+    } else if let Some(Command::Log { name }) = cli.command {
+        SearchAndExecute::open_log(name.as_deref())
+    } else if let Some(Command::Last { no_color }) = cli.command {
+        exit_with(SearchAndExecute::rerun_last(no_color)?)
+    } else if let Some(Command::History { count }) = cli.command {
+        SearchAndExecute::print_history(count)
+    } else if let Some(command_line) = cli.cli_content {
         SearchAndExecute::autocomplete(&command_line);
         Ok(())
     } else {
-        let context = SearchAndExecute::from(cli);
-        context.ensure_db_exists()?;
-        context.find_test().and_then(|test| context.run_test(test))
+        exit_with(TestBatch::from(cli).run()?)
     }
 }
@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+const REGEX_PREFIX: &str = "re:";
+
+/// A pattern selecting tests by name. Either a shell style glob, which must match the whole name,
+/// or a regex prefixed with `re:`, which may match any part of it.
+pub struct NamePattern {
+    regex: Regex,
+}
+
+impl NamePattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let expr = match pattern.strip_prefix(REGEX_PREFIX) {
+            Some(expr) => expr.to_owned(),
+            None => glob_to_regex(pattern),
+        };
+        let regex = Regex::new(&expr).with_context(|| format!("invalid pattern {pattern}"))?;
+        Ok(Self { regex })
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+/// Translates `*`, `?` and `[...]` to their regex equivalents, every other char matches itself.
+fn glob_to_regex(glob: &str) -> String {
+    let mut expr = String::from("^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => expr.push_str(".*"),
+            '?' => expr.push('.'),
+            '[' => {
+                expr.push('[');
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '!' if expr.ends_with('[') => expr.push('^'),
+                        '\\' | '[' => {
+                            expr.push('\\');
+                            expr.push(c);
+                        }
+                        c => expr.push(c),
+                    }
+                }
+                expr.push(']');
+            }
+            c => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr.push('$');
+    expr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        NamePattern::parse(pattern).unwrap().matches(name)
+    }
+
+    #[test]
+    fn star_matches_any_run_of_chars() {
+        assert!(matches("*_compaction_*", "test_compaction_basic"));
+        assert!(matches("test_*", "test_"));
+        assert!(!matches("*_compaction_*", "test_compactions"));
+    }
+
+    #[test]
+    fn question_mark_matches_a_single_char() {
+        assert!(matches("te?t", "test"));
+        assert!(!matches("te?t", "tet"));
+        assert!(!matches("te?t", "teest"));
+    }
+
+    #[test]
+    fn brackets_match_a_set_of_chars() {
+        assert!(matches("test_[ab]", "test_a"));
+        assert!(!matches("test_[ab]", "test_c"));
+        assert!(matches("test_[!ab]", "test_c"));
+        assert!(!matches("test_[!ab]", "test_a"));
+        assert!(matches("test_[a-c]x", "test_bx"));
+    }
+
+    #[test]
+    fn regex_metachars_in_a_glob_match_themselves() {
+        assert!(matches("a.b", "a.b"));
+        assert!(!matches("a.b", "axb"));
+        assert!(matches("Suite.name+", "Suite.name+"));
+        assert!(matches("f(x)|y", "f(x)|y"));
+        assert!(!matches("f(x)|y", "y"));
+    }
+
+    #[test]
+    fn globs_match_the_whole_name() {
+        assert!(!matches("compaction", "test_compaction"));
+        assert!(!matches("test", "test_compaction"));
+        assert!(matches("test_compaction", "test_compaction"));
+    }
+
+    #[test]
+    fn re_prefix_takes_a_regex_matching_any_part() {
+        assert!(matches("re:compaction", "test_compaction_basic"));
+        assert!(matches("re:^test_(a|b)$", "test_b"));
+        assert!(!matches("re:^test_(a|b)$", "test_c"));
+        assert!(matches("re:a.b", "axb"));
+    }
+
+    #[test]
+    fn invalid_regexes_are_errors() {
+        assert!(NamePattern::parse("re:(").is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::fmt::Display;
+use std::io::{stderr, stdin, stdout, IsTerminal, Write};
+
+use anyhow::{anyhow, Result};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{read, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+
+use crate::fuzzy::fuzzy_match;
+
+/// Rows below the list showing the preview of the highlighted item.
+const PREVIEW_ROWS: u16 = 6;
+
+/// An item which can be picked from a list.
+pub trait Pick: Display {
+    /// A single line naming the item, which is matched against the typed filter.
+    fn title(&self) -> String;
+
+    /// Lines describing the item, shown while it is highlighted.
+    fn preview(&self) -> Vec<String>;
+}
+
+/// Asks for one of the items to be picked. On a terminal the items are shown in an interactive
+/// picker, otherwise as a numbered list. Returns an error if nothing is picked. If stdin is not
+/// interactive nothing is asked, and the error lists the items followed by the hint.
+pub fn select_from_list<T: Pick>(items: Vec<T>, header: &str, hint: &str) -> Result<Option<T>> {
+    if items.is_empty() {
+        return Ok(None);
+    }
+
+    if !stdin().is_terminal() {
+        let mut message = header.to_owned();
+        for (index, item) in items.iter().enumerate() {
+            message.push_str(&format!("\n[{}] {}", index + 1, item));
+        }
+        return Err(anyhow!("{message}\nstdin is not interactive, {hint}"));
+    }
+
+    if stderr().is_terminal() {
+        pick_interactively(items, header)
+    } else {
+        pick_by_number(items, header)
+    }
+}
+
+fn pick_by_number<T: Display>(items: Vec<T>, header: &str) -> Result<Option<T>> {
+    println!("{header}");
+    loop {
+        println!("Please select one of the following matching items (q to quit): ");
+        for (index, item) in items.iter().enumerate() {
+            println!("[{}] {}", index + 1, item);
+        }
+        let mut buf = String::new();
+
+        print!(">> ");
+        stdout().flush()?;
+
+        if stdin().read_line(&mut buf)? == 0 {
+            return Err(anyhow!("no test selected!"));
+        }
+
+        let buf = buf.trim().to_lowercase();
+        if buf == "q" {
+            return Err(anyhow!("no test selected!"));
+        }
+
+        match buf.parse::<usize>() {
+            Ok(choice) if (1..=items.len()).contains(&choice) => {
+                return Ok(items.into_iter().nth(choice - 1));
+            }
+            _ => println!("that is an invalid choice!"),
+        }
+    }
+}
+
+/// Puts the terminal in raw mode on the alternate screen, and restores it when dropped, including
+/// when the picker fails.
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        let screen = Self;
+        execute!(stderr(), EnterAlternateScreen, Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let _ = execute!(stderr(), Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+struct Picker<'a, T> {
+    items: &'a [T],
+    titles: Vec<String>,
+    query: String,
+    /// The items matching the query, best match first, with the matched chars of their titles
+    filtered: Vec<(usize, Vec<usize>)>,
+    /// The highlighted entry of `filtered`
+    selected: usize,
+    /// The first entry of `filtered` on screen
+    scroll: usize,
+    /// How many entries fit on screen
+    page: usize,
+}
+
+impl<'a, T: Pick> Picker<'a, T> {
+    fn new(items: &'a [T]) -> Self {
+        let mut picker = Self {
+            items,
+            titles: items.iter().map(Pick::title).collect(),
+            query: String::new(),
+            filtered: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            page: 1,
+        };
+        picker.filter();
+        picker
+    }
+
+    fn filter(&mut self) {
+        let mut ranked: Vec<_> = self
+            .titles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, title)| {
+                if self.query.is_empty() {
+                    return Some((0, index, Vec::new()));
+                }
+                fuzzy_match(&self.query, title).map(|found| (found.score, index, found.positions))
+            })
+            .collect();
+        // a stable sort keeps equally good matches in their original order
+        ranked.sort_by_key(|(score, ..)| Reverse(*score));
+        self.filtered = ranked
+            .into_iter()
+            .map(|(_, index, positions)| (index, positions))
+            .collect();
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.filtered.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn current(&self) -> Option<usize> {
+        self.filtered.get(self.selected).map(|(index, _)| *index)
+    }
+
+    fn draw(&mut self, header: &str) -> Result<()> {
+        let (width, height) = size()?;
+        let columns = width as usize;
+        self.page = height.saturating_sub(PREVIEW_ROWS + 3).max(1) as usize;
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + self.page {
+            self.scroll = self.selected + 1 - self.page;
+        }
+
+        let mut out = stderr();
+        queue!(
+            out,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            Print(truncate(header, columns)),
+            MoveTo(0, 1),
+            Print(truncate(
+                &format!(
+                    "> {}  ({}/{})",
+                    self.query,
+                    self.filtered.len(),
+                    self.items.len()
+                ),
+                columns
+            )),
+        )?;
+
+        let shown = self.filtered.iter().enumerate().skip(self.scroll);
+        for (row, (entry, (index, positions))) in shown.take(self.page).enumerate() {
+            queue!(out, MoveTo(0, row as u16 + 2))?;
+            if entry == self.selected {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            let title: Vec<char> = self.titles[*index].chars().take(columns).collect();
+            for (position, c) in title.iter().enumerate() {
+                if positions.contains(&position) {
+                    queue!(
+                        out,
+                        SetAttribute(Attribute::Bold),
+                        Print(c),
+                        SetAttribute(Attribute::NormalIntensity)
+                    )?;
+                } else {
+                    queue!(out, Print(c))?;
+                }
+            }
+            if entry == self.selected {
+                queue!(out, Print(" ".repeat(columns - title.len())))?;
+            }
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+
+        let rule = self.page as u16 + 2;
+        queue!(out, MoveTo(0, rule), Print("─".repeat(columns)))?;
+        if let Some(index) = self.current() {
+            let preview = self.items[index].preview();
+            for (row, line) in preview.iter().take(PREVIEW_ROWS as usize).enumerate() {
+                queue!(
+                    out,
+                    MoveTo(0, rule + 1 + row as u16),
+                    Print(truncate(line, columns))
+                )?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+fn truncate(line: &str, columns: usize) -> String {
+    line.chars().take(columns).collect()
+}
+
+/// Shows the items in a list which can be moved through with the arrow keys and narrowed down by
+/// typing, with a preview of the highlighted item below it.
+fn pick_interactively<T: Pick>(items: Vec<T>, header: &str) -> Result<Option<T>> {
+    let picked = {
+        let _screen = RawScreen::enter()?;
+        let mut picker = Picker::new(&items);
+        loop {
+            picker.draw(header)?;
+            // anything else, such as the terminal being resized, just redraws the picker
+            let Event::Key(key) = read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+
+            let control = key.modifiers.contains(KeyModifiers::CONTROL);
+            let page = picker.page as isize;
+            match key.code {
+                KeyCode::Enter if picker.current().is_some() => break picker.current(),
+                KeyCode::Esc => break None,
+                KeyCode::Char('c') if control => break None,
+                KeyCode::Up => picker.move_by(-1),
+                KeyCode::Char('p') if control => picker.move_by(-1),
+                KeyCode::Down => picker.move_by(1),
+                KeyCode::Char('n') if control => picker.move_by(1),
+                KeyCode::PageUp => picker.move_by(-page),
+                KeyCode::PageDown => picker.move_by(page),
+                KeyCode::Char('u') if control => {
+                    picker.query.clear();
+                    picker.filter();
+                }
+                KeyCode::Backspace if !picker.query.is_empty() => {
+                    picker.query.pop();
+                    picker.filter();
+                }
+                KeyCode::Char(c) if !control => {
+                    picker.query.push(c);
+                    picker.filter();
+                }
+                _ => {}
+            }
+        }
+    };
+
+    match picked {
+        Some(index) => Ok(items.into_iter().nth(index)),
+        None => Err(anyhow!("no test selected!")),
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::{self, stdin};
+use std::os::fd::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{killpg, signal, SigHandler, Signal};
+use nix::unistd::{getpgrp, setpgid, tcgetpgrp, tcsetpgrp, Pid};
+
+/// How often a child with a deadline is checked for exit.
+const WAIT_POLL: Duration = Duration::from_millis(50);
+
+/// How long a timed out process group has to exit after SIGTERM before it is sent SIGKILL.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+fn owns_terminal() -> bool {
+    tcgetpgrp(stdin().as_raw_fd())
+        .map(|pgrp| pgrp == getpgrp())
+        .unwrap_or(false)
+}
+
+/// Makes the command the leader of a new process group when it is spawned, so that it can be killed
+/// along with everything it starts. If snipe is in the foreground of a terminal the new group takes
+/// it over, so that keyboard signals such as ctrl-c reach the test rather than snipe. Returns true
+/// if the terminal must be handed back with [`reclaim_terminal`] once the child exits.
+pub fn use_new_process_group(command: &mut Command) -> bool {
+    let foreground = owns_terminal();
+    unsafe {
+        command.pre_exec(move || {
+            setpgid(Pid::from_raw(0), Pid::from_raw(0))?;
+            if foreground {
+                // changing the foreground group from a background group raises SIGTTOU
+                signal(Signal::SIGTTOU, SigHandler::SigIgn)?;
+                tcsetpgrp(0, getpgrp())?;
+                signal(Signal::SIGTTOU, SigHandler::SigDfl)?;
+            }
+            Ok(())
+        });
+    }
+    foreground
+}
+
+/// Makes snipe's process group the foreground group of the terminal again.
+pub fn reclaim_terminal() {
+    unsafe {
+        let previous = signal(Signal::SIGTTOU, SigHandler::SigIgn);
+        let _ = tcsetpgrp(stdin().as_raw_fd(), getpgrp());
+        if let Ok(previous) = previous {
+            let _ = signal(Signal::SIGTTOU, previous);
+        }
+    }
+}
+
+/// Waits for a child which leads its own process group. If it is still running once the timeout
+/// expires the whole group is sent SIGTERM, then SIGKILL if the child has not exited after a grace
+/// period. Returns `None` if the child timed out.
+pub fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> io::Result<Option<ExitStatus>> {
+    // a timeout too long to be represented is as good as none
+    let Some(deadline) = timeout.and_then(|timeout| Instant::now().checked_add(timeout)) else {
+        return child.wait().map(Some);
+    };
+
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        thread::sleep(WAIT_POLL);
+    }
+
+    let group = Pid::from_raw(child.id() as i32);
+    let _ = killpg(group, Signal::SIGTERM);
+    let grace = Instant::now() + KILL_GRACE;
+    while Instant::now() < grace && child.try_wait()?.is_none() {
+        thread::sleep(WAIT_POLL);
+    }
+    // the child may have exited on SIGTERM while processes it started did not
+    let _ = killpg(group, Signal::SIGKILL);
+    child.wait()?;
+    Ok(None)
+}
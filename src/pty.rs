@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{self, stdin, stdout, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::Result;
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::pty::{openpty, OpenptyResult, Winsize};
+use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg, Termios};
+use nix::unistd::{read, setsid};
+
+use crate::run_log::RunLog;
+
+/// How long the stdin forwarder waits for input before checking if the child has exited.
+const STDIN_POLL_MS: i32 = 100;
+
+/// Puts the terminal attached to stdin in raw mode for as long as it is alive, so that key presses
+/// reach the child unmodified and echoing is left to the child's terminal.
+struct RawModeGuard {
+    original: Termios,
+}
+
+impl RawModeGuard {
+    fn new() -> Option<Self> {
+        let fd = stdin().as_raw_fd();
+        let original = tcgetattr(fd).ok()?;
+        let mut raw = original.clone();
+        cfmakeraw(&mut raw);
+        tcsetattr(fd, SetArg::TCSANOW, &raw).ok()?;
+        Some(Self { original })
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = tcsetattr(stdin().as_raw_fd(), SetArg::TCSANOW, &self.original);
+    }
+}
+
+fn window_size() -> Option<Winsize> {
+    let mut size = Winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let res = unsafe { libc::ioctl(stdout().as_raw_fd(), libc::TIOCGWINSZ, &mut size) };
+    (res == 0).then_some(size)
+}
+
+/// A child process attached to a pseudo terminal. Output written by the child to the terminal is
+/// copied to stdout and the run log, and stdin is copied to the terminal, until the child exits.
+pub struct PtyChild {
+    pub child: Child,
+    output: thread::JoinHandle<io::Result<()>>,
+    input: thread::JoinHandle<()>,
+    done: Arc<AtomicBool>,
+    _raw_mode: Option<RawModeGuard>,
+}
+
+impl PtyChild {
+    /// Spawns the command as the leader of a new session, with the pty as its controlling terminal.
+    pub fn spawn(mut command: Command, log: RunLog) -> Result<Self> {
+        let OpenptyResult { master, slave } = openpty(window_size().as_ref(), None)?;
+        let master = unsafe { File::from_raw_fd(master) };
+        let slave = unsafe { File::from_raw_fd(slave) };
+
+        command
+            .stdin(slave.try_clone()?)
+            .stdout(slave.try_clone()?)
+            .stderr(slave);
+        unsafe {
+            command.pre_exec(|| {
+                setsid()?;
+                if libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        // the command holds the parent's copies of the slave, which must be closed so that reads
+        // from the master fail once the child exits
+        drop(command);
+
+        let raw_mode = RawModeGuard::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let output = {
+            let master = master.try_clone()?;
+            thread::spawn(move || copy_output(master, log))
+        };
+        let input = {
+            let done = done.clone();
+            thread::spawn(move || copy_input(master, done))
+        };
+
+        Ok(Self {
+            child,
+            output,
+            input,
+            done,
+            _raw_mode: raw_mode,
+        })
+    }
+
+    /// Waits for the output to be drained once the child has exited, and stops forwarding input.
+    pub fn finish(self) -> Result<()> {
+        self.done.store(true, Ordering::Relaxed);
+        let output = self.output.join().expect("pty output thread panicked");
+        self.input.join().expect("pty input thread panicked");
+        output.map_err(anyhow::Error::from)
+    }
+}
+
+fn copy_output(mut master: File, log: RunLog) -> io::Result<()> {
+    let mut buf = [0u8; 4096];
+    let mut out = stdout();
+    loop {
+        match master.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                log.write(&buf[..n]);
+                out.write_all(&buf[..n])?;
+                out.flush()?;
+            }
+            // linux reports EIO on the master once every copy of the slave is closed
+            Err(err) if err.raw_os_error() == Some(Errno::EIO as i32) => return Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn copy_input(mut master: File, done: Arc<AtomicBool>) {
+    let input = stdin();
+    let mut buf = [0u8; 1024];
+    while !done.load(Ordering::Relaxed) {
+        let mut fds = [PollFd::new(input.as_raw_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, STDIN_POLL_MS) {
+            Ok(0) | Err(Errno::EINTR) => continue,
+            Ok(_) => {}
+            Err(_) => return,
+        }
+
+        let ready = fds[0].revents().unwrap_or(PollFlags::empty());
+        if !ready.contains(PollFlags::POLLIN) {
+            return;
+        }
+
+        match read(input.as_raw_fd(), &mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => {
+                if master.write_all(&buf[..n]).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -6,15 +6,25 @@ use std::{
 
 use anyhow::Result;
 use rustpython_parser::{
-    ast::{ExprCall, StmtClassDef, StmtFunctionDef, Suite},
+    ast::{ExprCall, Ranged, StmtClassDef, StmtFunctionDef, Suite},
+    source_code::RandomLocator,
+    text_size::TextSize,
     Parse,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::location::Location;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PyTest {
+    pub(crate) name: String,
+    pub(crate) location: Location,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClassWithTests {
     pub(crate) source_path: PathBuf,
-    pub(crate) tests: Vec<String>,
+    pub(crate) tests: Vec<PyTest>,
     pub(crate) class_name: String,
 }
 
@@ -32,11 +42,12 @@ impl Display for ClassWithTests {
 pub fn find_tests_in_source(p: &Path) -> Result<Vec<ClassWithTests>> {
     let content = fs::read_to_string(p)?;
     let program = Suite::parse(&content, &p.to_string_lossy())?;
+    let mut locator = RandomLocator::new(&content);
     let mut test_classes = Vec::new();
     for stmt in program {
         match stmt {
             rustpython_parser::ast::Stmt::ClassDef(class) => {
-                let tests = collect_test_fns(&class);
+                let tests = collect_test_fns(&class, p, &mut locator);
                 if !tests.is_empty() {
                     let class = ClassWithTests {
                         source_path: p.to_owned(),
@@ -52,13 +63,29 @@ pub fn find_tests_in_source(p: &Path) -> Result<Vec<ClassWithTests>> {
     Ok(test_classes)
 }
 
-fn collect_test_fns(class: &StmtClassDef) -> Vec<String> {
+fn collect_test_fns(class: &StmtClassDef, p: &Path, locator: &mut RandomLocator) -> Vec<PyTest> {
     let mut tests = Vec::new();
     for item in &class.body {
         match item {
             rustpython_parser::ast::Stmt::FunctionDef(f) => {
                 if is_test_fn(&f) {
-                    tests.push(f.name.to_string());
+                    // the test starts at the `@` of its first decorator, editors often leave the
+                    // cursor on it
+                    let start = match f.decorator_list.first() {
+                        Some(decorator) => decorator.start() - TextSize::from(1),
+                        None => f.range.start(),
+                    };
+                    let position = locator.locate(start);
+                    let end = locator.locate(f.range.end());
+                    tests.push(PyTest {
+                        name: f.name.to_string(),
+                        location: Location {
+                            file: p.to_owned(),
+                            line: position.row.get() as usize,
+                            column: position.column.get() as usize,
+                            end_line: end.row.get() as usize,
+                        },
+                    });
                 }
             }
             _ => {}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chrono::Local;
+
+use crate::config::{get_data_file_path, get_prefix};
+
+const RUNS_DIR: &str = "runs";
+
+/// Older logs are removed when a new one is created, trace logs can take up a lot of space.
+const MAX_RUN_LOGS: usize = 50;
+
+fn sanitize(test_name: &str) -> String {
+    test_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_.-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The combined output of all the commands of a single run, stored as
+/// `<data dir>/runs/<timestamp>-<test>.log`. Clones write to the same file.
+#[derive(Clone)]
+pub struct RunLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl RunLog {
+    pub fn create(test_name: &str, envs: &HashMap<String, String>) -> Result<Self> {
+        prune_logs()?;
+        let now = Local::now();
+        let file_name = format!(
+            "{}-{}.log",
+            now.format("%Y%m%d-%H%M%S%.3f"),
+            sanitize(test_name)
+        );
+        let path = get_prefix()?.place_data_file(Path::new(RUNS_DIR).join(file_name))?;
+        let mut file = File::create(&path)?;
+
+        let command_line: Vec<_> = std::env::args().collect();
+        writeln!(file, "# test: {test_name}")?;
+        writeln!(file, "# started: {}", now.to_rfc3339())?;
+        writeln!(file, "# snipe: {}", shell_words::join(command_line))?;
+        let mut envs: Vec<_> = envs.iter().collect();
+        envs.sort();
+        for (key, value) in envs {
+            writeln!(file, "# env: {key}={value}")?;
+        }
+
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends to the log. Failing to write the log should not interrupt the test, so errors are
+    /// ignored.
+    pub fn write(&self, data: &[u8]) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(data);
+        }
+    }
+
+    /// Deletes the log, for runs whose output is not worth keeping.
+    pub fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+
+    pub fn record_command(&self, template: &str, command: &str) {
+        self.write(format!("# {template}: {command}\n").as_bytes());
+    }
+}
+
+fn list_logs() -> Result<Vec<PathBuf>> {
+    let dir = get_data_file_path(RUNS_DIR)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut logs: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map(|ext| ext == "log").unwrap_or(false))
+        .collect();
+    // the timestamp prefix makes the names sort in the order the runs were started
+    logs.sort();
+    Ok(logs)
+}
+
+fn prune_logs() -> Result<()> {
+    let logs = list_logs()?;
+    if logs.len() >= MAX_RUN_LOGS {
+        for log in &logs[..=logs.len() - MAX_RUN_LOGS] {
+            fs::remove_file(log)?;
+        }
+    }
+    Ok(())
+}
+
+/// The log of the most recent run.
+pub fn latest_log() -> Result<Option<PathBuf>> {
+    Ok(list_logs()?.pop())
+}
@@ -1,103 +1,163 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-use anyhow::anyhow;
 use walkdir::WalkDir;
 
 use crate::cmake_parser::parse_tests_from_file;
-use crate::cmake_parser::structures::RpTest;
-
-pub enum SplitOn {
-    Delim(&'static str),
-}
+use crate::cmake_parser::structures::{CcTestCase, RpTest, TestFramework};
+use crate::config::{ScanConfig, TestMacro};
+use crate::location::Location;
+use crate::scanners::cpp_lexer::{self, MacroInvocation};
 
 pub struct CcTest {
     tag: String,
-    name: String,
+    args: Vec<String>,
+    line: usize,
+    column: usize,
+    end_line: usize,
 }
 
-impl CcTest {
-    pub fn new(tag: &str, name: &str) -> Self {
+impl From<MacroInvocation> for CcTest {
+    fn from(invocation: MacroInvocation) -> Self {
         Self {
-            tag: tag.to_owned(),
-            name: name.to_owned(),
+            tag: invocation.name,
+            args: invocation.args,
+            line: invocation.line,
+            column: invocation.column,
+            end_line: invocation.end_line,
         }
     }
 }
 
+const SUITE_OPEN_TAGS: [&str; 2] = ["BOOST_AUTO_TEST_SUITE", "BOOST_FIXTURE_TEST_SUITE"];
+
+const SUITE_CLOSE_TAG: &str = "BOOST_AUTO_TEST_SUITE_END";
+
+fn to_test_case(
+    test: CcTest,
+    test_macro: &TestMacro,
+    suites: &[String],
+    test_source: &Path,
+) -> Option<CcTestCase> {
+    let name = test.args.get(test_macro.name_arg)?.to_owned();
+    let suite = match test_macro.suite_arg {
+        Some(suite_arg) => Some(test.args.get(suite_arg)?.to_owned()),
+        None if test_macro.framework != TestFramework::GTest && !suites.is_empty() => {
+            Some(suites.join("/"))
+        }
+        None => None,
+    };
+    Some(CcTestCase {
+        name,
+        suite,
+        framework: test_macro.framework.clone(),
+        parameterized: test_macro.parameterized,
+        location: Location {
+            file: test_source.to_owned(),
+            line: test.line,
+            column: test.column,
+            end_line: test.end_line,
+        },
+    })
+}
+
+/// Finds invocations of `tags` in C++ source, ignoring comments, string literals and regions
+/// disabled by the preprocessor given the set of `defines`.
 pub fn parse_test_name_from_source(
     data: &str,
     tags: &HashSet<String>,
-    split_args_on: SplitOn,
-) -> anyhow::Result<Vec<CcTest>> {
-    let mut arg_groups = Vec::new();
-    let mut lines = data.lines();
-    while let Some(line) = lines.next() {
-        let line = line.trim();
-        for tag in tags {
-            if line.starts_with(tag) {
-                let mut buf = String::new();
-                buf.push_str(line);
-                while !buf.contains(')') {
-                    buf.push_str(lines.next().ok_or(anyhow!("missing closing paren"))?);
-                }
-                let args: String = buf.split('(').skip(1).collect();
-                let args = args.split(')').next().unwrap_or("");
-                let args: Vec<_> = match split_args_on {
-                    SplitOn::Delim(delim) => args
-                        .split(delim)
-                        .map(str::trim)
-                        .map(str::to_owned)
-                        .collect(),
-                };
-
-                arg_groups.push(CcTest::new(tag, &args[0]));
-            }
-        }
-    }
-    Ok(arg_groups)
+    defines: &HashSet<String>,
+) -> Vec<CcTest> {
+    let stripped = cpp_lexer::strip_comments_and_literals(data);
+    let active = cpp_lexer::remove_inactive_regions(&stripped, defines);
+    cpp_lexer::find_macro_invocations(&active, tags)
+        .into_iter()
+        .map(CcTest::from)
+        .collect()
 }
 
-pub fn find_tests_in_cc_source(test_source: &Path) -> anyhow::Result<HashSet<String>> {
-    let mut tests = HashSet::new();
+pub fn find_tests_in_cc_source(
+    test_source: &Path,
+    scan_config: &ScanConfig,
+) -> anyhow::Result<HashSet<CcTestCase>> {
     let data = fs::read_to_string(test_source)?;
-    let tags = [
-        "FIXTURE_TEST",
-        "SEASTAR_THREAD_TEST_CASE",
-        "SEASTAR_TEST_CASE",
-        "BOOST_AUTO_TEST_CASE",
-    ]
-    .into_iter()
-    .map(str::to_owned)
-    .collect();
-    let tests_and_tags = parse_test_name_from_source(&data, &tags, SplitOn::Delim(","))?;
+    Ok(find_tests_in_cc_data(&data, test_source, scan_config))
+}
+
+/// Finds the test cases in the contents of a C++ source file, with Boost and Seastar tests named
+/// by the `BOOST_AUTO_TEST_SUITE` blocks enclosing them.
+fn find_tests_in_cc_data(
+    data: &str,
+    test_source: &Path,
+    scan_config: &ScanConfig,
+) -> HashSet<CcTestCase> {
+    let mut tests = HashSet::new();
+    let macros_by_name: HashMap<_, _> = scan_config
+        .cc_test_macros
+        .iter()
+        .map(|m| (m.name.as_str(), m))
+        .collect();
+    let tags = macros_by_name
+        .keys()
+        .copied()
+        .chain(SUITE_OPEN_TAGS)
+        .chain([SUITE_CLOSE_TAG])
+        .map(str::to_owned)
+        .collect();
+    let tests_and_tags = parse_test_name_from_source(data, &tags, &scan_config.cc_defines);
+    let mut suites = Vec::new();
     for test in tests_and_tags {
-        println!("found test {} of type: {}", test.name, test.tag);
-        tests.insert(test.name);
+        let tag = test.tag.clone();
+        if SUITE_OPEN_TAGS.contains(&tag.as_str()) {
+            suites.push(test.args.into_iter().next().unwrap_or_default());
+            continue;
+        }
+        if tag == SUITE_CLOSE_TAG {
+            if suites.pop().is_none() {
+                eprintln!("unbalanced {SUITE_CLOSE_TAG} in {:?}", test_source);
+            }
+            continue;
+        }
+        let Some(test_macro) = macros_by_name.get(tag.as_str()) else {
+            continue;
+        };
+        match to_test_case(test, test_macro, &suites, test_source) {
+            Some(test) => {
+                eprintln!(
+                    "found {} test {} of type: {} at {}",
+                    test.framework,
+                    test.qualified_name(),
+                    tag,
+                    test.location
+                );
+                tests.insert(test);
+            }
+            None => eprintln!("skipping {tag} invocation with missing arguments"),
+        }
     }
-    Ok(tests)
+    tests
 }
 
-pub fn collect_cmake_test_definitions(root: &str) -> anyhow::Result<Vec<RpTest>> {
+pub fn collect_cmake_test_definitions(scan_config: &ScanConfig) -> anyhow::Result<Vec<RpTest>> {
     let mut collected_tests = Vec::new();
-    for entry in WalkDir::new(root) {
+    for entry in WalkDir::new(&scan_config.cc_test_root) {
         let entry = entry?;
         let path = entry.path();
         if path.is_file() && path.file_name().unwrap().to_string_lossy() == "CMakeLists.txt" {
             let parent = path.parent().unwrap();
             if parent.file_name().unwrap() == "tests" {
-                println!("collecting tests from {:?}", path);
+                eprintln!("collecting tests from {:?}", path);
                 let mut tests = parse_tests_from_file(path)?;
-                println!("found {} test suites", tests.len());
+                eprintln!("found {} test suites", tests.len());
                 for t in tests.iter_mut() {
                     for source in &t.sources {
                         let mut path = parent.to_owned();
                         path.push(source);
-                        println!("looking for tests in {:?}", path);
-                        let tests_in_file = find_tests_in_cc_source(&path)?;
+                        eprintln!("looking for tests in {:?}", path);
+                        let tests_in_file = find_tests_in_cc_source(&path, scan_config)?;
                         t.tests.extend(tests_in_file.into_iter());
-                        println!("found {} tests in {:?}", t.tests.len(), path);
+                        eprintln!("found {} tests in {:?}", t.tests.len(), path);
                     }
                 }
                 collected_tests.extend(tests.into_iter());
@@ -107,3 +167,125 @@ pub fn collect_cmake_test_definitions(root: &str) -> anyhow::Result<Vec<RpTest>>
 
     Ok(collected_tests)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::cmake_parser::structures::TestKind;
+
+    fn scan(data: &str) -> HashSet<CcTestCase> {
+        find_tests_in_cc_data(data, Path::new("foo_test.cc"), &ScanConfig::default())
+    }
+
+    /// The qualified names of the tests found in the source, sorted.
+    fn qualified_names(data: &str) -> Vec<String> {
+        let mut names: Vec<_> = scan(data).iter().map(|t| t.qualified_name()).collect();
+        names.sort();
+        names
+    }
+
+    fn binary(data: &str) -> RpTest {
+        RpTest {
+            name: "foo".to_owned(),
+            sources: HashSet::from(["foo_test.cc".to_owned()]),
+            kind: TestKind::Unit,
+            tests: scan(data),
+            cmake_file: PathBuf::from("CMakeLists.txt"),
+        }
+    }
+
+    #[test]
+    fn boost_tests_are_named_by_nested_suites() {
+        let data = "BOOST_AUTO_TEST_SUITE(outer)
+BOOST_FIXTURE_TEST_SUITE(inner, fixture)
+BOOST_AUTO_TEST_CASE(a) {}
+BOOST_AUTO_TEST_SUITE_END()
+SEASTAR_THREAD_TEST_CASE(b) {}
+BOOST_AUTO_TEST_SUITE_END()
+BOOST_AUTO_TEST_CASE(c) {}
+";
+        assert_eq!(qualified_names(data), ["c", "outer/b", "outer/inner/a"]);
+    }
+
+    #[test]
+    fn unbalanced_suite_end_is_ignored() {
+        let data = "BOOST_AUTO_TEST_SUITE_END()
+BOOST_AUTO_TEST_CASE(a) {}
+BOOST_AUTO_TEST_SUITE(s)
+BOOST_AUTO_TEST_CASE(b) {}
+BOOST_AUTO_TEST_SUITE_END()
+BOOST_AUTO_TEST_SUITE_END()
+BOOST_AUTO_TEST_CASE(c) {}
+";
+        assert_eq!(qualified_names(data), ["a", "c", "s/b"]);
+    }
+
+    #[test]
+    fn unclosed_suite_names_the_rest_of_the_file() {
+        let data = "BOOST_AUTO_TEST_SUITE(s)\nBOOST_AUTO_TEST_CASE(a) {}\n";
+        assert_eq!(qualified_names(data), ["s/a"]);
+    }
+
+    #[test]
+    fn gtest_tests_are_named_by_their_own_suite() {
+        let data = "BOOST_AUTO_TEST_SUITE(ignored)
+TEST(Alpha, plain) {}
+TEST_F(Fixture, with_fixture) {}
+BOOST_AUTO_TEST_SUITE_END()
+";
+        assert_eq!(
+            qualified_names(data),
+            ["Alpha.plain", "Fixture.with_fixture"]
+        );
+    }
+
+    #[test]
+    fn parameterized_macros_are_marked() {
+        let data = "TEST(A, plain) {}
+TEST_P(A, param) {}
+TYPED_TEST(A, typed) {}
+BOOST_AUTO_TEST_CASE(boost_plain) {}
+BOOST_AUTO_TEST_CASE_TEMPLATE(tmpl, T, types) {}
+";
+        let mut parameterized: Vec<_> = scan(data)
+            .into_iter()
+            .filter(|t| t.parameterized)
+            .map(|t| t.qualified_name())
+            .collect();
+        parameterized.sort();
+        assert_eq!(parameterized, ["A.param", "A.typed", "tmpl"]);
+    }
+
+    #[test]
+    fn cases_are_found_by_leaf_or_qualified_name() {
+        let test = binary(
+            "BOOST_AUTO_TEST_SUITE(a)
+BOOST_AUTO_TEST_CASE(same) {}
+BOOST_AUTO_TEST_CASE(only_a) {}
+BOOST_AUTO_TEST_SUITE_END()
+BOOST_AUTO_TEST_SUITE(b)
+BOOST_AUTO_TEST_CASE(same) {}
+BOOST_AUTO_TEST_SUITE_END()
+TEST(Gtest, case) {}
+",
+        );
+        let found = |name| {
+            test.find_cases(name)
+                .iter()
+                .map(|t| t.qualified_name())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("only_a"), ["a/only_a"]);
+        assert_eq!(found("a/only_a"), ["a/only_a"]);
+        assert_eq!(found("same"), ["a/same", "b/same"]);
+        assert_eq!(found("b/same"), ["b/same"]);
+        assert_eq!(found("case"), ["Gtest.case"]);
+        assert_eq!(found("Gtest.case"), ["Gtest.case"]);
+        assert!(found("b/only_a").is_empty());
+        assert!(test.find_case("same").is_none());
+        assert!(test.find_case("a/same").is_some());
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::HashSet;
+
+/// A macro invocation found in C++ source, with its top level, comma separated arguments. `line`
+/// and `column` are the one based position of the macro name. `end_line` is the line of the brace
+/// closing the body which follows the invocation, or of the closing paren if there is no body.
+pub struct MacroInvocation {
+    pub name: String,
+    pub args: Vec<String>,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn blank(c: char) -> char {
+    if c == '\n' {
+        '\n'
+    } else {
+        ' '
+    }
+}
+
+/// Replaces comments and the contents of string and character literals with whitespace. Newlines
+/// are kept so that line numbers in the output match the input.
+pub fn strip_comments_and_literals(data: &str) -> String {
+    let chars: Vec<char> = data.chars().collect();
+    let mut out = String::with_capacity(data.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+        match (c, next) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    out.push(' ');
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                out.push_str("  ");
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    out.push(blank(chars[i]));
+                    i += 1;
+                }
+                out.push_str("  ");
+                i += 2;
+            }
+            ('"', _) if prev == Some('R') => {
+                // raw string literal: R"delim( ... )delim"
+                let delimiter: String = chars[i + 1..].iter().take_while(|c| **c != '(').collect();
+                let terminator: Vec<char> = format!("){delimiter}\"").chars().collect();
+                out.push('"');
+                i += 1;
+                while i < chars.len() && !chars[i..].starts_with(&terminator) {
+                    out.push(blank(chars[i]));
+                    i += 1;
+                }
+                for _ in 0..terminator.len().saturating_sub(1) {
+                    out.push(' ');
+                }
+                out.push('"');
+                i += terminator.len();
+            }
+            ('"', _) | ('\'', _) => {
+                out.push(c);
+                i += 1;
+                while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        out.push(' ');
+                        i += 1;
+                    }
+                    out.push(blank(chars[i]));
+                    i += 1;
+                }
+                if i < chars.len() {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+            }
+            (d, _) if d.is_ascii_digit() && !prev.map(is_ident_char).unwrap_or(false) => {
+                // numbers may contain digit separators, eg 1'000'000, which are not char literals
+                while i < chars.len() && (is_ident_char(chars[i]) || "'.".contains(chars[i])) {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+struct ConditionalBranch {
+    parent_active: bool,
+    active: bool,
+    taken: bool,
+}
+
+/// Evaluates the condition of an `#if` or `#elif`. Only literal numbers, `defined` checks and
+/// single identifiers are understood, anything more complex is assumed to be true so that tests
+/// are not dropped because of an expression we cannot evaluate.
+fn evaluate_condition(expr: &str, defines: &HashSet<String>) -> bool {
+    let expr = expr.trim();
+    if ["&&", "||", "<", ">", "=", "+", "-"]
+        .iter()
+        .any(|op| expr.contains(op))
+    {
+        return true;
+    }
+
+    if let Some(rest) = expr.strip_prefix('!') {
+        return !evaluate_condition(rest, defines);
+    }
+
+    if let Some(rest) = expr.strip_prefix("defined") {
+        let name = rest.trim().trim_start_matches('(').trim_end_matches(')');
+        return defines.contains(name.trim());
+    }
+
+    if let Ok(value) = expr.parse::<i64>() {
+        return value != 0;
+    }
+
+    if !expr.is_empty() && expr.chars().all(is_ident_char) {
+        return defines.contains(expr);
+    }
+
+    true
+}
+
+/// Blanks out preprocessor directives and the regions disabled by `#if`, `#ifdef` and friends.
+/// Expects input which has already been through [`strip_comments_and_literals`].
+pub fn remove_inactive_regions(data: &str, defines: &HashSet<String>) -> String {
+    let mut out = Vec::new();
+    let mut branches: Vec<ConditionalBranch> = Vec::new();
+    let mut in_directive = false;
+    for line in data.lines() {
+        let trimmed = line.trim();
+        if in_directive {
+            in_directive = trimmed.ends_with('\\');
+            out.push("");
+            continue;
+        }
+
+        let Some(directive) = trimmed.strip_prefix('#') else {
+            let active = branches.last().map(|b| b.active).unwrap_or(true);
+            out.push(if active { line } else { "" });
+            continue;
+        };
+
+        in_directive = trimmed.ends_with('\\');
+        out.push("");
+
+        let directive = directive.trim_start();
+        let keyword: String = directive
+            .chars()
+            .take_while(|c| is_ident_char(*c))
+            .collect();
+        let argument = directive[keyword.len()..].trim_end_matches('\\').trim();
+        let current = branches.last().map(|b| b.active).unwrap_or(true);
+        match keyword.as_str() {
+            "if" | "ifdef" | "ifndef" => {
+                let condition = match keyword.as_str() {
+                    "ifdef" => defines.contains(argument),
+                    "ifndef" => !defines.contains(argument),
+                    _ => evaluate_condition(argument, defines),
+                };
+                branches.push(ConditionalBranch {
+                    parent_active: current,
+                    active: current && condition,
+                    taken: condition,
+                });
+            }
+            "elif" => {
+                if let Some(branch) = branches.last_mut() {
+                    let condition = !branch.taken && evaluate_condition(argument, defines);
+                    branch.active = branch.parent_active && condition;
+                    branch.taken |= condition;
+                }
+            }
+            "else" => {
+                if let Some(branch) = branches.last_mut() {
+                    branch.active = branch.parent_active && !branch.taken;
+                    branch.taken = true;
+                }
+            }
+            "endif" => {
+                branches.pop();
+            }
+            _ => {}
+        }
+    }
+    out.join("\n")
+}
+
+/// Splits the text between a pair of parens on commas which are not nested in another pair of
+/// parens, brackets or braces. Returns `None` if the closing paren is never found.
+fn parse_arguments(chars: &[char]) -> Option<(Vec<String>, usize)> {
+    let mut depth = 0;
+    let mut args = Vec::new();
+    let mut current = String::new();
+    for (offset, c) in chars.iter().enumerate() {
+        match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                if depth > 1 {
+                    current.push(*c);
+                }
+            }
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    args.push(current.trim().to_owned());
+                    return Some((args, offset + 1));
+                }
+                current.push(*c);
+            }
+            ',' if depth == 1 => {
+                args.push(current.trim().to_owned());
+                current.clear();
+            }
+            c => current.push(*c),
+        }
+    }
+    None
+}
+
+fn count_lines(chars: &[char]) -> usize {
+    chars.iter().filter(|c| **c == '\n').count()
+}
+
+/// Counts the line breaks up to the brace closing a body which starts the input, after any
+/// whitespace. Returns zero if there is no body, or it is never closed.
+fn lines_of_body(chars: &[char]) -> usize {
+    let open = chars.iter().take_while(|c| c.is_whitespace()).count();
+    if chars.get(open) != Some(&'{') {
+        return 0;
+    }
+
+    let mut depth = 0;
+    for (offset, c) in chars[open..].iter().enumerate() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return count_lines(&chars[..open + offset]);
+                }
+            }
+            _ => {}
+        }
+    }
+    0
+}
+
+/// Finds invocations of the given macros anywhere in the input, which should already be stripped
+/// of comments, literals and inactive regions. Arguments may span several lines.
+pub fn find_macro_invocations(data: &str, names: &HashSet<String>) -> Vec<MacroInvocation> {
+    let chars: Vec<char> = data.chars().collect();
+    let mut invocations = Vec::new();
+    let mut i = 0;
+    // the line number of `line_start`, which is the index of the first char on that line
+    let mut line = 1;
+    let mut line_start = 0;
+    while i < chars.len() {
+        if chars[i] == '\n' {
+            line += 1;
+            line_start = i + 1;
+        }
+
+        if !is_ident_char(chars[i]) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < chars.len() && is_ident_char(chars[i]) {
+            i += 1;
+        }
+        let ident: String = chars[start..i].iter().collect();
+        if !names.contains(&ident) {
+            continue;
+        }
+
+        let open = i + chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+        if chars.get(open) != Some(&'(') {
+            continue;
+        }
+
+        match parse_arguments(&chars[open..]) {
+            Some((args, consumed)) => {
+                let end = open + consumed;
+                invocations.push(MacroInvocation {
+                    name: ident,
+                    args,
+                    line,
+                    column: start - line_start + 1,
+                    end_line: line + count_lines(&chars[i..end]) + lines_of_body(&chars[end..]),
+                });
+                for (offset, c) in chars[i..end].iter().enumerate() {
+                    if *c == '\n' {
+                        line += 1;
+                        line_start = i + offset + 1;
+                    }
+                }
+                i = end;
+            }
+            None => {
+                eprintln!("ignoring {ident} invocation without a closing paren");
+                break;
+            }
+        }
+    }
+    invocations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the whole pipeline used by the C++ scanner, returning each invocation as its name and
+    /// arguments, with the lines it starts and ends on.
+    fn scan(data: &str, defines: &[&str]) -> Vec<(String, usize, usize)> {
+        let names = ["TEST", "SEASTAR_THREAD_TEST_CASE"]
+            .map(str::to_owned)
+            .into();
+        let defines = defines.iter().map(|d| d.to_string()).collect();
+        let stripped = strip_comments_and_literals(data);
+        let active = remove_inactive_regions(&stripped, &defines);
+        find_macro_invocations(&active, &names)
+            .into_iter()
+            .map(|m| {
+                (
+                    format!("{}({})", m.name, m.args.join(", ")),
+                    m.line,
+                    m.end_line,
+                )
+            })
+            .collect()
+    }
+
+    fn names(data: &str, defines: &[&str]) -> Vec<String> {
+        scan(data, defines)
+            .into_iter()
+            .map(|(name, ..)| name)
+            .collect()
+    }
+
+    #[test]
+    fn line_comments_are_ignored() {
+        let data = "// TEST(A, commented) {}\nTEST(A, real) {} // TEST(A, trailing)\n";
+        assert_eq!(scan(data, &[]), [("TEST(A, real)".to_owned(), 2, 2)]);
+    }
+
+    #[test]
+    fn block_comments_are_ignored() {
+        let data = "/* TEST(A, one) {\n} */ TEST(A, two) {}\n/**/TEST(A, three) {}";
+        assert_eq!(names(data, &[]), ["TEST(A, two)", "TEST(A, three)"]);
+    }
+
+    #[test]
+    fn if_zero_regions_are_skipped() {
+        let data =
+            "#if 0\nTEST(A, off) {}\n#else\nTEST(A, on) {}\n#endif\n#if 1\nTEST(A, one) {}\n#endif";
+        assert_eq!(names(data, &[]), ["TEST(A, on)", "TEST(A, one)"]);
+    }
+
+    #[test]
+    fn ifdef_regions_follow_the_defines() {
+        let data = "#ifdef FLAG\nTEST(A, flagged) {}\n#else\nTEST(A, unflagged) {}\n#endif\n\
+                    #ifndef FLAG\nTEST(A, not_flagged) {}\n#endif";
+        assert_eq!(
+            names(data, &[]),
+            ["TEST(A, unflagged)", "TEST(A, not_flagged)"]
+        );
+        assert_eq!(names(data, &["FLAG"]), ["TEST(A, flagged)"]);
+    }
+
+    #[test]
+    fn string_and_char_literals_are_ignored() {
+        let data = r#"auto s = "TEST(A, in_string)"; char c = ')'; char q = '"'; TEST(A, real) {}"#;
+        assert_eq!(names(data, &[]), ["TEST(A, real)"]);
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_end_a_string() {
+        let data = r#"auto s = "\" TEST(A, escaped)"; TEST(A, real) {}"#;
+        assert_eq!(names(data, &[]), ["TEST(A, real)"]);
+    }
+
+    #[test]
+    fn raw_string_literals_are_ignored() {
+        let data = "auto r = R\"x(TEST(A, raw) )\" still raw)x\";\nTEST(A, real) {}";
+        assert_eq!(scan(data, &[]), [("TEST(A, real)".to_owned(), 2, 2)]);
+    }
+
+    #[test]
+    fn digit_separators_are_not_char_literals() {
+        let data = "int n = 1'000'000; TEST(A, after_number) {} char c = 'x';";
+        assert_eq!(names(data, &[]), ["TEST(A, after_number)"]);
+    }
+
+    #[test]
+    fn arguments_may_span_lines_and_nest() {
+        let data = "\nTEST(Suite,\n     name) {}\nSEASTAR_THREAD_TEST_CASE(f(a, b)) {}";
+        assert_eq!(
+            scan(data, &[]),
+            [
+                ("TEST(Suite, name)".to_owned(), 2, 3),
+                ("SEASTAR_THREAD_TEST_CASE(f(a, b))".to_owned(), 4, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn define_continuations_are_skipped() {
+        let data = "#define WRAP(x) \\\n    TEST(A, x)\nTEST(A, real) {}";
+        assert_eq!(scan(data, &[]), [("TEST(A, real)".to_owned(), 3, 3)]);
+    }
+
+    #[test]
+    fn end_line_is_the_closing_brace_of_the_body() {
+        let data =
+            "TEST(A, body) {\n    if (x) {\n        f(\"}\");\n    }\n}\n\nTEST(A, no_body);\n";
+        assert_eq!(
+            scan(data, &[]),
+            [
+                ("TEST(A, body)".to_owned(), 1, 5),
+                ("TEST(A, no_body)".to_owned(), 7, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn column_is_the_start_of_the_macro_name() {
+        let active = strip_comments_and_literals("namespace { }  TEST(A, b) {}");
+        let names = ["TEST".to_owned()].into();
+        let invocations = find_macro_invocations(&active, &names);
+        assert_eq!(invocations[0].column, 16);
+    }
+}
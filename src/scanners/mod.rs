@@ -1,2 +1,3 @@
 pub(crate) mod cmake;
+pub(crate) mod cpp_lexer;
 pub(crate) mod python;
@@ -1,16 +1,25 @@
 use std::collections::HashMap;
 use std::env;
-use std::io::{BufRead, BufReader};
-use std::process::{Command, Stdio};
+use std::io::{self, stderr, stdout, BufRead, BufReader, IsTerminal, Read, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
+use chrono::Local;
 use handlebars::{no_escape, Handlebars};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::cmake_parser::structures::{RpTest, TestKind};
-use crate::config::CommandRunConfig;
+use crate::cmake_parser::structures::{CcTestCase, RpTest, TestFramework};
+use crate::config::{CommandRunConfig, Runner};
+use crate::history::{self, RunRecord, RunTarget};
 use crate::parse_env_file;
+use crate::process_group::{reclaim_terminal, use_new_process_group, wait_with_timeout};
+use crate::pty::PtyChild;
 use crate::py_parser::ClassWithTests;
+use crate::run_log::RunLog;
 
 fn load_build_type() -> String {
     let default = "DEBUG".to_owned();
@@ -25,11 +34,123 @@ fn load_build_type() -> String {
         .to_owned()
 }
 
-fn build_cc_command(
+/// A rendered command, along with the name of the template it was rendered from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellCommand {
+    pub template: String,
+    pub command: String,
+}
+
+impl ShellCommand {
+    fn render<T: Serialize>(h: &Handlebars, template: &str, data: &T) -> Result<Self> {
+        Ok(Self {
+            template: template.to_owned(),
+            command: h.render(template, data)?,
+        })
+    }
+
+    /// Renders a template which runs a test, making the arguments passed after `--` on the snipe
+    /// command line available as `{{extra_args}}`. If the template does not use them they are
+    /// appended to the rendered command.
+    fn render_with_extra_args(
+        h: &Handlebars,
+        command_config: &CommandRunConfig,
+        template: &str,
+        mut data: serde_json::Value,
+        extra_args: &[String],
+    ) -> Result<Self> {
+        let extra_args = shell_words::join(extra_args);
+        data["extra_args"] = json!(extra_args);
+        let mut rendered = Self::render(h, template, &data)?;
+        let uses_extra_args = command_config
+            .command_mappings
+            .get(template)
+            .map(|t| t.contains("extra_args"))
+            .unwrap_or(false);
+        if !uses_extra_args && !extra_args.is_empty() {
+            rendered.command = format!("{} {extra_args}", rendered.command);
+        }
+        Ok(rendered)
+    }
+}
+
+/// The argument selecting the given tests in a test binary. The tests must all take the same kind
+/// of filter: Boost and Seastar tests are selected with `-t`, googletest ones with `--gtest_filter`.
+fn test_filter_arg(tests: &[&CcTestCase]) -> String {
+    let patterns: Vec<_> = tests
+        .iter()
+        .map(|test| match test.framework {
+            TestFramework::GTest if test.parameterized => {
+                let suite = test.suite.as_deref().unwrap_or("*");
+                format!("*/{suite}.{0}/*:{suite}/*.{0}", test.name)
+            }
+            // Boost names the cases of a template test `name<T>`
+            _ if test.parameterized => format!("{}*", test.qualified_name()),
+            _ => test.qualified_name(),
+        })
+        .collect();
+    match tests.first().map(|test| &test.framework) {
+        Some(TestFramework::GTest) => format!("--gtest_filter={}", patterns.join(":")),
+        _ => format!("-t {}", patterns.join(":")),
+    }
+}
+
+/// Picks `run_<framework>` when the user has configured one, so that eg gtest binaries can be
+/// launched differently from Boost ones, falling back to the shared `run` template.
+pub(crate) fn run_template_for(
+    command_config: &CommandRunConfig,
+    framework: &TestFramework,
+) -> String {
+    let specific = format!("run_{}", framework.template_name());
+    if command_config.command_mappings.contains_key(&specific) {
+        specific
+    } else {
+        "run".to_owned()
+    }
+}
+
+/// The framework of the tests, if they all use the same one. A binary mixing frameworks, or with no
+/// known tests, is run with the shared `run` template.
+fn shared_framework<'a>(tests: &[&'a CcTestCase]) -> Option<&'a TestFramework> {
+    let (first, rest) = tests.split_first()?;
+    rest.iter()
+        .all(|test| test.framework == first.framework)
+        .then_some(&first.framework)
+}
+
+/// The tests to run from a suite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Selection {
+    /// The named tests
+    Tests(Vec<String>),
+    /// Every test in the C++ binary or ducktape class
+    All,
+    /// Every test in the file defining the ducktape class
+    File,
+}
+
+impl Selection {
+    /// A name for the run in logs and the history.
+    fn label(&self, target: &RunTarget) -> String {
+        match (self, target) {
+            (Selection::Tests(names), _) => names.join(","),
+            (_, RunTarget::Binary { name, .. }) => name.clone(),
+            (Selection::All, RunTarget::Class { name, .. }) => name.clone(),
+            (Selection::File, RunTarget::Class { source_path, .. }) => {
+                source_path.to_string_lossy().into()
+            }
+        }
+    }
+}
+
+/// Builds the commands compiling the binary and running the selected tests from it, with a single
+/// invocation of the binary.
+pub(crate) fn build_cc_command(
     test: RpTest,
-    test_name: String,
+    selection: &Selection,
     command_config: &CommandRunConfig,
-) -> Result<Vec<String>> {
+    extra_args: &[String],
+) -> Result<Vec<ShellCommand>> {
     let mut h = Handlebars::new();
     h.register_escape_fn(no_escape);
 
@@ -39,13 +160,10 @@ fn build_cc_command(
     let mut commands = Vec::with_capacity(h.get_templates().len());
 
     let build_type = load_build_type();
-    let test_obj = match test.kind {
-        TestKind::Unit => format!("{}_rpunit", test.name),
-        TestKind::Fixture => format!("{}_rpfixture", test.name),
-        TestKind::Bench => format!("{}_rpbench", test.name),
-    };
+    let test_obj = test.binary_name();
 
-    commands.push(h.render(
+    commands.push(ShellCommand::render(
+        &h,
         "compile",
         &json!({
             "build_type": build_type,
@@ -53,25 +171,53 @@ fn build_cc_command(
         }),
     )?);
 
-    let test_tag_arg = format!("-t {test_name}");
+    // the whole binary is run without a filter, even if no test was found in it by the scan
+    let (test_cases, test_tag_arg) = match selection {
+        Selection::Tests(names) => {
+            let test_cases = names
+                .iter()
+                .map(|name| match test.find_cases(name)[..] {
+                    [case] => Ok(case),
+                    [] => Err(anyhow!("{name} is not a test in {test}")),
+                    _ => Err(anyhow!("{name} matches several tests in {test}")),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let test_tag_arg = test_filter_arg(&test_cases);
+            (test_cases, test_tag_arg)
+        }
+        Selection::All | Selection::File => (test.tests.iter().collect(), String::new()),
+    };
+    let framework = shared_framework(&test_cases);
+    let run_template = match framework {
+        Some(framework) => run_template_for(command_config, framework),
+        None => "run".to_owned(),
+    };
+    let framework = framework.map_or("", TestFramework::template_name);
     let pwd = env::current_dir()?;
-    commands.push(h.render(
-        "run",
-        &json!({
+    commands.push(ShellCommand::render_with_extra_args(
+        &h,
+        command_config,
+        &run_template,
+        json!({
                 "build_type": build_type,
                 "test_obj": test_obj,
                 "test_tag_arg": test_tag_arg,
+                "framework": framework,
                 "pwd": pwd.to_string_lossy(),
         }),
+        extra_args,
     )?);
     Ok(commands)
 }
 
-fn build_py_command(
+/// Builds the ducktape command, which runs the selected tests `repeat` times.
+pub(crate) fn build_py_command(
     test: ClassWithTests,
-    test_name: String,
+    selection: &Selection,
     command_config: &CommandRunConfig,
-) -> Result<Vec<String>> {
+    extra_args: &[String],
+    repeat: u64,
+) -> Result<Vec<ShellCommand>> {
     let mut h = Handlebars::new();
     h.register_escape_fn(no_escape);
     for (k, v) in &command_config.command_mappings {
@@ -79,97 +225,606 @@ fn build_py_command(
     }
     let mut commands = Vec::with_capacity(h.get_templates().len());
 
-    let test_path = format!(
-        "{}::{}.{}",
-        test.source_path.to_string_lossy(),
-        test.class_name,
-        test_name
-    );
+    let source_path = test.source_path.to_string_lossy();
+    let test_path = match selection {
+        Selection::Tests(names) => names
+            .iter()
+            .map(|name| format!("{source_path}::{}.{name}", test.class_name))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Selection::All => format!("{source_path}::{}", test.class_name),
+        Selection::File => source_path.to_string(),
+    };
 
-    commands.push(h.render(
+    commands.push(ShellCommand::render_with_extra_args(
+        &h,
+        command_config,
         "duck",
-        &json!({
+        json!({
             "test_path": test_path,
-            "test_args": "--repeat=1",
+            "test_args": format!("--repeat={repeat}"),
         }),
+        extra_args,
     )?);
 
     Ok(commands)
 }
 
+/// Exit codes of the compile commands already run by this invocation of snipe, so that a binary
+/// shared by several of the requested tests is only compiled once.
+pub type CompileResults = HashMap<String, i32>;
+
+/// State shared by the runs of all the tests requested together.
+#[derive(Default)]
+pub struct BatchState {
+    pub compiled: CompileResults,
+    /// The commands of a JSON dry run, printed as a single document once every test has been
+    /// looked at
+    pub dry_run_commands: Vec<serde_json::Value>,
+}
+
+/// How many times the run steps of a test are repeated. The compile step is only run once.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Repeat {
+    #[default]
+    Once,
+    /// Run up to this many times, stopping at the first failure
+    Times(u64),
+    UntilFail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DryRun {
+    Off,
+    Shell,
+    Json,
+}
+
+/// Options controlling how the rendered commands for a test are run.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub edit: bool,
+    pub dry_run: DryRun,
+    /// Passed through to the test, from everything after `--` on the command line
+    pub extra_args: Vec<String>,
+    pub runner: Runner,
+    pub repeat: Repeat,
+    /// Kills the test if a run takes longer, overriding the configured default for its kind
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            edit: false,
+            dry_run: DryRun::Off,
+            extra_args: Vec::new(),
+            runner: Runner::default(),
+            repeat: Repeat::Once,
+            timeout: None,
+        }
+    }
+}
+
+/// Runs the selected tests from the binary, see [`build_cc_command`].
 pub fn run_cc_test(
     test: RpTest,
-    test_name: String,
-    edit: bool,
+    selection: Selection,
+    options: &RunOptions,
     command_config: &CommandRunConfig,
     envs: &HashMap<String, String>,
-) -> Result<()> {
+    batch: &mut BatchState,
+) -> Result<i32> {
+    let options = RunOptions {
+        timeout: options
+            .timeout
+            .or_else(|| command_config.default_timeout(&test.kind.to_string())),
+        ..options.clone()
+    };
+    let target = RunTarget::from(&test);
     run_shell_commands(
-        build_cc_command(test, test_name, command_config)?,
-        edit,
+        build_cc_command(test, &selection, command_config, &options.extra_args)?,
+        &target,
+        &selection,
+        &options,
         envs,
+        batch,
     )
 }
 
 pub fn run_py_test(
     test: ClassWithTests,
-    test_name: String,
-    edit: bool,
+    selection: Selection,
+    options: &RunOptions,
     command_config: &CommandRunConfig,
     envs: &HashMap<String, String>,
-) -> Result<()> {
+    batch: &mut BatchState,
+) -> Result<i32> {
+    // ducktape can repeat a test by itself, so only repeating until failure is done by snipe
+    let (repeat, mut options) = match options.repeat {
+        Repeat::Times(n) => (
+            n,
+            RunOptions {
+                repeat: Repeat::Once,
+                ..options.clone()
+            },
+        ),
+        _ => (1, options.clone()),
+    };
+    options.timeout = options
+        .timeout
+        .or_else(|| command_config.default_timeout("ducktape"));
+    let target = RunTarget::from(&test);
     run_shell_commands(
-        build_py_command(test, test_name, command_config)?,
-        edit,
+        build_py_command(
+            test,
+            &selection,
+            command_config,
+            &options.extra_args,
+            repeat,
+        )?,
+        &target,
+        &selection,
+        &options,
         envs,
+        batch,
     )
 }
 
-fn edit_commands(commands: Vec<String>) -> Result<Vec<String>> {
+/// Runs the commands of a previous run again, as they were rendered then.
+pub(crate) fn rerun(
+    record: RunRecord,
+    options: &RunOptions,
+    envs: &HashMap<String, String>,
+) -> Result<i32> {
+    run_shell_commands(
+        record.commands,
+        &record.target,
+        &record.selection,
+        options,
+        envs,
+        &mut BatchState::default(),
+    )
+}
+
+fn edit_commands(commands: Vec<ShellCommand>) -> Result<Vec<ShellCommand>> {
     let mut editor = rustyline::DefaultEditor::new()?;
     let mut new_commands = Vec::with_capacity(commands.len());
     for command in commands {
-        let edited = editor.readline_with_initial("Edit command >> ", (&command, ""))?;
-        new_commands.push(edited);
+        let edited = editor.readline_with_initial("Edit command >> ", (&command.command, ""))?;
+        new_commands.push(ShellCommand {
+            command: edited,
+            ..command
+        });
     }
     Ok(new_commands)
 }
 
+/// Prints the commands in a form which can be pasted into a shell, with the environment set as
+/// `KEY=VAL` prefixes.
+fn print_commands(commands: &[ShellCommand], envs: &HashMap<String, String>) {
+    let mut envs: Vec<_> = envs.iter().collect();
+    envs.sort();
+    let prefix: Vec<_> = envs
+        .iter()
+        .map(|(k, v)| format!("{k}={}", shell_words::quote(v)))
+        .collect();
+    for command in commands {
+        let line: Vec<_> = prefix
+            .iter()
+            .map(String::as_str)
+            .chain([command.command.as_str()])
+            .collect();
+        println!("{}", line.join(" "));
+    }
+}
+
+/// The commands as JSON objects, each with the environment it is run with.
+fn commands_as_json(
+    commands: &[ShellCommand],
+    envs: &HashMap<String, String>,
+) -> Vec<serde_json::Value> {
+    let env: serde_json::Map<_, _> = envs
+        .iter()
+        .map(|(k, v)| (k.to_string(), json!(v)))
+        .collect();
+    commands
+        .iter()
+        .map(|c| json!({"template": c.template, "command": c.command, "env": env}))
+        .collect()
+}
+
+/// The exit code of a run which was killed for taking too long, the same as that of `timeout(1)`.
+pub(crate) const TIMED_OUT_EXIT_CODE: i32 = 124;
+
+/// The exit code of a finished process, using the shell convention of `128 + signal` for
+/// processes killed by a signal.
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+/// Spawns the process, in a new process group if it has to be killed on timeout. Returns true if
+/// the process group took over the terminal.
+fn spawn_process(process: &mut Command, timeout: Option<Duration>) -> Result<(Child, bool)> {
+    let foreground = timeout.is_some() && use_new_process_group(process);
+    Ok((process.spawn()?, foreground))
+}
+
+fn wait_process(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    foreground: bool,
+) -> Result<Option<ExitStatus>> {
+    let status = wait_with_timeout(child, timeout);
+    if foreground {
+        reclaim_terminal();
+    }
+    Ok(status?)
+}
+
+fn run_with_teetty(
+    command: &ShellCommand,
+    timeout: Option<Duration>,
+    envs: &HashMap<String, String>,
+    log: &RunLog,
+) -> Result<Option<ExitStatus>> {
+    let command_str = format!("-s -- {}", command.command);
+    let tokens = shell_words::split(&command_str)?;
+    let mut process = Command::new("teetty");
+    process.args(tokens).stdout(Stdio::piped()).envs(envs);
+    let (mut child, foreground) = spawn_process(&mut process, timeout)?;
+    let o = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("failed to get command output for: {command_str}"))?;
+
+    let output = {
+        let log = log.clone();
+        thread::spawn(move || -> io::Result<()> {
+            for line in BufReader::new(o).lines() {
+                let line = line?;
+                log.write(format!("{line}\n").as_bytes());
+                println!("{}", line)
+            }
+            Ok(())
+        })
+    };
+    let status = wait_process(&mut child, timeout, foreground)?;
+    output
+        .join()
+        .expect("teetty output thread panicked")
+        .map_err(|err| anyhow!("failed to read output of {command_str}: {err}"))?;
+    Ok(status)
+}
+
+fn build_process(command: &ShellCommand, envs: &HashMap<String, String>) -> Result<Command> {
+    let tokens = shell_words::split(&command.command)?;
+    let (program, args) = tokens
+        .split_first()
+        .ok_or_else(|| anyhow!("empty {} command", command.template))?;
+    let mut process = Command::new(program);
+    process.args(args).envs(envs);
+    Ok(process)
+}
+
+fn run_with_pty(
+    command: &ShellCommand,
+    timeout: Option<Duration>,
+    envs: &HashMap<String, String>,
+    log: &RunLog,
+) -> Result<Option<ExitStatus>> {
+    // the child leads a new session, and so its own process group
+    let mut child = PtyChild::spawn(build_process(command, envs)?, log.clone())?;
+    let status = wait_with_timeout(&mut child.child, timeout)?;
+    child.finish()?;
+    Ok(status)
+}
+
+fn tee(mut from: impl Read, mut to: impl Write, log: &RunLog) -> io::Result<()> {
+    let mut buf = [0u8; 4096];
+    loop {
+        match from.read(&mut buf)? {
+            0 => return Ok(()),
+            n => {
+                log.write(&buf[..n]);
+                to.write_all(&buf[..n])?;
+                to.flush()?;
+            }
+        }
+    }
+}
+
+fn run_with_pipes(
+    command: &ShellCommand,
+    timeout: Option<Duration>,
+    envs: &HashMap<String, String>,
+    log: &RunLog,
+) -> Result<Option<ExitStatus>> {
+    let mut process = build_process(command, envs)?;
+    process.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let (mut child, foreground) = spawn_process(&mut process, timeout)?;
+    let errors = child.stderr.take().map(|errors| {
+        let log = log.clone();
+        thread::spawn(move || tee(errors, stderr(), &log))
+    });
+    let output = child.stdout.take().map(|output| {
+        let log = log.clone();
+        thread::spawn(move || tee(output, stdout(), &log))
+    });
+    let status = wait_process(&mut child, timeout, foreground)?;
+    for forwarder in [output, errors].into_iter().flatten() {
+        forwarder
+            .join()
+            .expect("output forwarding thread panicked")?;
+    }
+    Ok(status)
+}
+
+fn run_plain(
+    command: &ShellCommand,
+    timeout: Option<Duration>,
+    envs: &HashMap<String, String>,
+    log: &RunLog,
+) -> Result<Option<ExitStatus>> {
+    log.write(b"# output is not captured with the Plain runner\n");
+    let (mut child, foreground) = spawn_process(&mut build_process(command, envs)?, timeout)?;
+    wait_process(&mut child, timeout, foreground)
+}
+
+/// Runs the command and returns its exit code, or [`TIMED_OUT_EXIT_CODE`] if it was killed because
+/// it ran for longer than `timeout`.
+fn run_shell_command(
+    command: &ShellCommand,
+    runner: Runner,
+    timeout: Option<Duration>,
+    envs: &HashMap<String, String>,
+    log: &RunLog,
+) -> Result<i32> {
+    log.record_command(&command.template, &command.command);
+    let status = match runner {
+        Runner::Teetty => run_with_teetty(command, timeout, envs, log),
+        Runner::Pty if stdout().is_terminal() => run_with_pty(command, timeout, envs, log),
+        Runner::Pty => run_with_pipes(command, timeout, envs, log),
+        Runner::Plain => run_plain(command, timeout, envs, log),
+    }?;
+    match status {
+        Some(status) => Ok(exit_code(status)),
+        None => {
+            let timeout = timeout.unwrap_or_default().as_secs();
+            log.write(format!("\n# timed out after {timeout}s\n").as_bytes());
+            eprintln!("{} step timed out after {timeout}s", command.template);
+            Ok(TIMED_OUT_EXIT_CODE)
+        }
+    }
+}
+
+/// Runs the commands in order with output saved to a new log, stopping at the first one which
+/// fails. Returns the exit code of the last command run.
+fn run_steps(
+    commands: &[ShellCommand],
+    test_name: &str,
+    options: &RunOptions,
+    envs: &HashMap<String, String>,
+    compiled: &mut CompileResults,
+) -> Result<(i32, RunLog)> {
+    let log = RunLog::create(test_name, envs)?;
+    for command in commands {
+        if let Some(code) = compiled.get(&command.command) {
+            log.write(
+                format!("# {}: already ran, exit code {code}\n", command.template).as_bytes(),
+            );
+            if *code != 0 {
+                return Ok((*code, log));
+            }
+            continue;
+        }
+
+        // compiling can legitimately take a long time, only the test itself is timed
+        let timeout = match command.template.as_str() {
+            "compile" => None,
+            _ => options.timeout,
+        };
+        let code = run_shell_command(command, options.runner, timeout, envs, &log)?;
+        if command.template == "compile" {
+            compiled.insert(command.command.clone(), code);
+        }
+        if code != 0 {
+            eprintln!(
+                "{} step failed with exit code {code}: {}",
+                command.template, command.command
+            );
+            return Ok((code, log));
+        }
+    }
+    Ok((0, log))
+}
+
+/// Runs the leading compile steps once, then the remaining steps repeatedly until one fails or the
+/// requested number of runs pass. Only the log of the failing run, or of the last run if none
+/// failed, is kept.
+fn run_repeatedly(
+    commands: &[ShellCommand],
+    test_name: &str,
+    options: &RunOptions,
+    envs: &HashMap<String, String>,
+    compiled: &mut CompileResults,
+) -> Result<(i32, RunLog)> {
+    let setup_len = commands
+        .iter()
+        .take_while(|command| command.template == "compile")
+        .count();
+    let (setup, steps) = commands.split_at(setup_len);
+    let (code, mut log) = run_steps(setup, test_name, options, envs, compiled)?;
+    if code != 0 {
+        return Ok((code, log));
+    }
+
+    let mut passed = 0;
+    let mut code = 0;
+    for run in 1.. {
+        match options.repeat {
+            Repeat::Times(n) if run > n => break,
+            Repeat::Times(n) => eprintln!("run {run}/{n}"),
+            _ => eprintln!("run {run}"),
+        }
+        let (run_code, run_log) = run_steps(steps, test_name, options, envs, compiled)?;
+        log.remove();
+        log = run_log;
+        code = run_code;
+        if code != 0 {
+            break;
+        }
+        passed += 1;
+    }
+
+    let failed = if code == 0 { 0 } else { 1 };
+    eprintln!("{} runs: {passed} passed, {failed} failed", passed + failed);
+    Ok((code, log))
+}
+
+/// Runs the commands in order, stopping at the first one which fails. Returns the exit code of the
+/// last command run, which is the test's exit code if all the preceding steps succeeded.
 fn run_shell_commands(
-    commands: Vec<String>,
-    edit: bool,
+    commands: Vec<ShellCommand>,
+    target: &RunTarget,
+    selection: &Selection,
+    options: &RunOptions,
     envs: &HashMap<String, String>,
-) -> Result<()> {
-    let commands = if edit {
+    batch: &mut BatchState,
+) -> Result<i32> {
+    let test_name = &selection.label(target);
+    let compiled = &mut batch.compiled;
+    let commands = if options.edit {
         edit_commands(commands)?
     } else {
         commands
     };
 
-    for command_str in commands {
-        let command_str = format!("-s -- {command_str}");
-        let tokens = shell_words::split(&command_str)?;
-        let mut command = Command::new("teetty")
-            .args(tokens)
-            .stdout(Stdio::piped())
-            .envs(envs)
-            .spawn()?;
-        let o = command
-            .stdout
-            .as_mut()
-            .ok_or_else(|| anyhow!("failed to get command output for: {command_str}"))?;
-
-        for line in BufReader::new(o).lines() {
-            match line {
-                Ok(line) => println!("{}", line),
-                Err(err) => {
-                    println!("failed to run command {command_str}: ");
-                    println!("{err}");
-                    return Ok(());
-                }
-            }
+    if options.dry_run != DryRun::Off {
+        let commands: Vec<_> = commands
+            .into_iter()
+            .filter(|c| c.template != "compile" || compiled.insert(c.command.clone(), 0).is_none())
+            .collect();
+        match options.dry_run {
+            DryRun::Json => batch
+                .dry_run_commands
+                .extend(commands_as_json(&commands, envs)),
+            _ => print_commands(&commands, envs),
+        }
+        return Ok(0);
+    }
+
+    let started_at = Local::now();
+    let timer = Instant::now();
+    let (code, log) = match options.repeat {
+        Repeat::Once => run_steps(&commands, test_name, options, envs, compiled)?,
+        _ => run_repeatedly(&commands, test_name, options, envs, compiled)?,
+    };
+    eprintln!("output saved to {}", log.path().to_string_lossy());
+
+    let record = RunRecord {
+        test_name: test_name.to_owned(),
+        target: target.clone(),
+        selection: selection.clone(),
+        commands,
+        exit_code: code,
+        started_at,
+        duration_ms: timer.elapsed().as_millis() as u64,
+        log: log.path().to_owned(),
+    };
+    if let Err(err) = history::record_run(record) {
+        eprintln!("failed to record the run in the history: {err}");
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::location::Location;
+
+    fn case(suite: Option<&str>, name: &str, framework: TestFramework) -> CcTestCase {
+        CcTestCase {
+            name: name.to_owned(),
+            suite: suite.map(str::to_owned),
+            framework,
+            parameterized: false,
+            location: Location {
+                file: PathBuf::from("foo_test.cc"),
+                line: 1,
+                column: 1,
+                end_line: 1,
+            },
         }
-        command.wait()?;
     }
-    Ok(())
+
+    fn parameterized(test: CcTestCase) -> CcTestCase {
+        CcTestCase {
+            parameterized: true,
+            ..test
+        }
+    }
+
+    fn filter(tests: &[CcTestCase]) -> String {
+        test_filter_arg(&tests.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn boost_and_seastar_tests_are_selected_by_path() {
+        assert_eq!(filter(&[case(None, "a", TestFramework::Boost)]), "-t a");
+        assert_eq!(
+            filter(&[case(Some("outer/inner"), "a", TestFramework::Seastar)]),
+            "-t outer/inner/a"
+        );
+    }
+
+    #[test]
+    fn boost_tests_are_combined_with_colons() {
+        let tests = [
+            case(None, "a", TestFramework::Boost),
+            case(Some("s"), "b", TestFramework::Boost),
+        ];
+        assert_eq!(filter(&tests), "-t a:s/b");
+    }
+
+    #[test]
+    fn boost_template_tests_match_every_type() {
+        let tests = [parameterized(case(Some("s"), "tmpl", TestFramework::Boost))];
+        assert_eq!(filter(&tests), "-t s/tmpl*");
+    }
+
+    #[test]
+    fn gtest_tests_are_selected_by_suite_and_name() {
+        let tests = [
+            case(Some("A"), "x", TestFramework::GTest),
+            case(Some("B"), "y", TestFramework::GTest),
+        ];
+        assert_eq!(filter(&tests), "--gtest_filter=A.x:B.y");
+    }
+
+    #[test]
+    fn gtest_parameterized_tests_match_every_instantiation() {
+        let tests = [
+            parameterized(case(Some("S"), "p", TestFramework::GTest)),
+            case(Some("A"), "x", TestFramework::GTest),
+        ];
+        assert_eq!(filter(&tests), "--gtest_filter=*/S.p/*:S/*.p:A.x");
+    }
+
+    #[test]
+    fn whole_binary_uses_a_framework_template_only_if_shared() {
+        let boost = case(None, "a", TestFramework::Boost);
+        let gtest = case(Some("A"), "x", TestFramework::GTest);
+        assert_eq!(
+            shared_framework(&[&boost, &boost]),
+            Some(&TestFramework::Boost)
+        );
+        assert_eq!(shared_framework(&[&boost, &gtest]), None);
+        assert_eq!(shared_framework(&[]), None);
+    }
 }